@@ -0,0 +1,43 @@
+use std::{cell::Cell, rc::Rc};
+
+use phyx_script::simulation::SceneBuilder;
+
+static SOURCE: &str = r#"
+fn hit(self) {
+    count_hit()
+}
+
+objects.add(object {
+    hit,
+    gravity: 3,
+    bounciness: 1
+})
+
+objects.add(object {
+    shape: ring,
+    fixed: true,
+    size: 100
+})
+"#;
+
+fn main() {
+    let hits = Rc::new(Cell::new(0));
+    let counter = hits.clone();
+
+    let mut scene = SceneBuilder::new()
+        .native("count_hit", "Count a hit on the Rust side", move || {
+            counter.set(counter.get() + 1);
+        })
+        .build(SOURCE.to_string());
+
+    for _ in 0..600 {
+        scene.step();
+
+        for collision in scene.collisions() {
+            println!("frame {}: object {} hit object {}", scene.frame(), collision.first, collision.second);
+        }
+    }
+
+    let ball = &scene.object_states()[0];
+    println!("ball at ({}, {}) after {} hits", ball.x, ball.y, hits.get());
+}
@@ -0,0 +1,105 @@
+use std::{cmp::Ordering, env, fs};
+
+#[cfg(feature = "audio")]
+use phyx_script::simulation::{play_audio, Audio};
+use phyx_script::simulation::{note_names, SceneBuilder, Scene};
+
+use nannou::{prelude::*, winit::window::Icon};
+
+static FPS: u128 = 60;
+static SECS_PER_FRAME: u128 = 1_000_000 / FPS;
+
+pub struct Model {
+    scene: Scene,
+    #[cfg(feature = "audio")]
+    audio_stream: nannou_audio::Stream<Audio>
+}
+
+fn set_icon(app: &App) {
+    let mut icon_path = app.assets_path().expect("Failed to find assets directory");
+    icon_path.push("icon.png");
+
+    let decoder = png::Decoder::new(fs::File::open(icon_path).unwrap());
+    let mut reader = decoder.read_info().expect("Failed to read info of icon");
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).expect("Failed to read the next frame");
+    let bytes = &buf[..info.buffer_size()].to_vec();
+    let icon = Icon::from_rgba(bytes.clone(), 180, 180).expect("Failed to create icon");
+    app.main_window().set_window_icon(Some(icon));
+}
+
+pub fn model(app: &App) -> Model {
+    let args: Vec<String> = env::args().collect();
+
+    match args.len().cmp(&2) {
+        Ordering::Less => panic!("Please input a file to run"),
+        Ordering::Greater => panic!("Too many arguments"),
+        Ordering::Equal => ()
+    }
+
+    let filename = &args[1];
+    let title = "Phyx - ".to_string() + filename.split('/').next_back().expect("Filename is empty");
+    app.main_window().set_title(title.as_str());
+
+    app.main_window().set_maximized(true);
+
+    set_icon(app);
+    let assets_path = app.assets_path().expect("Failed to find assets directory");
+    let notes = note_names(&assets_path);
+
+    let code = fs::read_to_string(filename).expect("Failed to read file");
+    let scene = SceneBuilder::new().notes(notes).build(code);
+
+    Model {
+        scene,
+        #[cfg(feature = "audio")]
+        audio_stream: audio_stream()
+    }
+}
+
+#[cfg(feature = "audio")]
+fn audio_stream() -> nannou_audio::Stream<Audio> {
+    let audio = Audio::new();
+
+    let audio_host = nannou_audio::Host::new();
+    let stream = audio_host
+        .new_output_stream(audio)
+        .render(play_audio)
+        .build()
+        .expect("Failed to build stream");
+
+    stream.play().expect("Failed to play");
+    stream
+}
+
+pub fn update(app: &App, model: &mut Model, _update: Update) {
+    let elapsed_frames = app.duration.since_start.as_nanos() / SECS_PER_FRAME;
+    if elapsed_frames < model.scene.frame() {
+        return
+    }
+
+    model.scene.step();
+
+    #[cfg(feature = "audio")]
+    {
+        let assets_path = app.assets_path().expect("Failed to get assets path");
+        for &id in model.scene.hits() {
+            if let Some(object) = model.scene.object(id) {
+                object.play_hit_note(&assets_path, &mut model.audio_stream);
+            }
+        }
+    }
+}
+
+pub fn exit(_app: &App, mut model: Model) {
+    model.scene.exit();
+}
+
+pub fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+
+    draw.background().color(Rgb::from(model.scene.background_color()));
+    model.scene.draw(&draw);
+
+    draw.to_frame(app, &frame).expect("Failed to draw to frame");
+}
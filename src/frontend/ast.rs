@@ -4,6 +4,7 @@ use std::{collections::HashMap, fmt::Debug};
 pub enum Statement {
     Program { body: Vec<Statement> },
     VarDeclaration { identifier: String, value: Box<Statement> },
+    GlobalDeclaration { identifier: String, value: Box<Statement> },
     FunctionDeclaration { name: String, parameters: Vec<String>, body: Vec<Statement> },
     ForLoop { loop_var: String, range: Box<Statement>, body: Vec<Statement> },
     If { condition: Box<Statement>, body: Vec<Statement>, else_body: Vec<Statement> },
@@ -20,6 +21,8 @@ pub enum Statement {
 
     Identifier(String),
     NumericLiteral(f32),
+    StringLiteral(String),
 
-    Object(HashMap<String, Statement>)
+    Object(HashMap<String, Statement>),
+    List(Vec<Statement>)
 }
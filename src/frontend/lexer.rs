@@ -1,10 +1,26 @@
 use phf::phf_map;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ShapeType {
     Circle,
     Rect,
-    Ring
+    Ring,
+    /// A part of a ring from the start angle to the end angle
+    Arc,
+    /// A rect with round ends along its longer side
+    Capsule,
+    /// Pointing up, with its base at the bottom of the rect
+    Triangle,
+    /// A rect with corners rounded by the radius
+    RoundedRect,
+    /// Only the outline of a rect
+    HollowRect,
+    /// The outline of the shape, scaled by the width and height of the object like the corners of a rect
+    Polygon(Vec<[f32; 2]>),
+    /// Only the outline of a polygon
+    HollowPolygon(Vec<[f32; 2]>),
+    /// Connected lines through the points, which are in world coordinates when the object is at the origin
+    Polyline(Vec<[f32; 2]>)
 }
 
 impl TryFrom<String> for ShapeType {
@@ -15,6 +31,11 @@ impl TryFrom<String> for ShapeType {
             "circle" => Ok(Self::Circle),
             "rect" => Ok(Self::Rect),
             "ring" => Ok(Self::Ring),
+            "arc" => Ok(Self::Arc),
+            "capsule" => Ok(Self::Capsule),
+            "triangle" => Ok(Self::Triangle),
+            "rounded_rect" => Ok(Self::RoundedRect),
+            "box" => Ok(Self::HollowRect),
             _ => Err(format!("{:?} is not a valid shape", value))
         }
     }
@@ -24,6 +45,7 @@ impl TryFrom<String> for ShapeType {
 pub enum Token {
     Number(String),
     Identifier(String),
+    String(String),
 
     BinaryOperator(String),
     BooleanOperator(String),
@@ -38,6 +60,9 @@ pub enum Token {
     OpenBracket,
     CloseBracket,
 
+    OpenSquareBracket,
+    CloseSquareBracket,
+
     Semicolon,
     Comma,
     Colon,
@@ -48,6 +73,7 @@ pub enum Token {
     Eof,
 
     Let,
+    Global,
     Fn,
     For,
     In,
@@ -60,6 +86,7 @@ pub enum Token {
 
 static KEYWORDS: phf::Map<&'static str, Token> = phf_map! {
     "let" => Token::Let,
+    "global" => Token::Global,
     "fn" => Token::Fn,
     "for" => Token::For,
     "in" => Token::In,
@@ -77,10 +104,31 @@ pub fn tokenize(source_code: String) -> Vec<Token> {
 
     while let Some(c) = chars.next() {
         match c {
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some('"') => string.push('"'),
+                            Some('\\') => string.push('\\'),
+                            c2 => panic!("Invalid escape sequence in string: '\\{:?}'", c2)
+                        },
+                        Some(c2) => string.push(c2),
+                        None => panic!("String {:?} was never closed", string)
+                    }
+                }
+
+                tokens.push(Token::String(string))
+            }
             '(' => tokens.push(Token::OpenParen),
             ')' => tokens.push(Token::CloseParen),
             '{' => tokens.push(Token::OpenBracket),
             '}' => tokens.push(Token::CloseBracket),
+            '[' => tokens.push(Token::OpenSquareBracket),
+            ']' => tokens.push(Token::CloseSquareBracket),
             ';' => tokens.push(Token::Semicolon),
             ':' => tokens.push(Token::Colon),
             ',' => tokens.push(Token::Comma),
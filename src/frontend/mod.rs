@@ -1,6 +1,6 @@
-pub(crate) mod ast;
+pub mod ast;
 mod lexer;
 mod parser;
 
-pub(crate) use lexer::ShapeType;
-pub(crate) use parser::Parser;
+pub use lexer::ShapeType;
+pub use parser::Parser;
@@ -7,6 +7,12 @@ pub struct Parser {
     tokens: Vec<Token>
 }
 
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Parser {
     pub fn new() -> Self {
         Self {
@@ -50,7 +56,7 @@ impl Parser {
 
     fn parse_statement(&mut self) -> Statement {
         match self.at() {
-            Token::Let => self.parse_var_declaration(),
+            Token::Let | Token::Global => self.parse_var_declaration(),
             Token::Fn => self.parse_function_declaration(),
             Token::For => self.parse_for_loop(),
             Token::If => self.parse_if_statement(),
@@ -60,17 +66,20 @@ impl Parser {
     }
 
     fn parse_var_declaration(&mut self) -> Statement {
-        self.eat();
+        let keyword = self.eat();
         let identifier = match self.eat() {
             Token::Identifier(name) => name,
-            token => panic!("Token {:?} after let isn't an identifier", token)
+            token => panic!("Token {:?} after {:?} isn't an identifier", token, keyword)
         };
 
         self.expect(Token::Equals, "Variable isn't set with equals sign".to_string());
 
-        let value = self.parse_statement();
+        let value = Box::new(self.parse_statement());
 
-        Statement::VarDeclaration { identifier, value: Box::new(value) }
+        match keyword {
+            Token::Global => Statement::GlobalDeclaration { identifier, value },
+            _ => Statement::VarDeclaration { identifier, value }
+        }
     }
 
     fn parse_function_declaration(&mut self) -> Statement {
@@ -358,8 +367,10 @@ impl Parser {
 
         match token {
             Token::Object => self.parse_object(),
+            Token::OpenSquareBracket => self.parse_list(),
             Token::Identifier(value) => Statement::Identifier(value),
             Token::Number(number) => Statement::NumericLiteral(number.parse().expect("Failed to parse")),
+            Token::String(string) => Statement::StringLiteral(string),
             Token::UnaryOperator(operator) => self.parse_unary_expr(operator),
             Token::OpenParen => {
                 let value = self.parse_expr();
@@ -373,6 +384,22 @@ impl Parser {
         }
     }
 
+    fn parse_list(&mut self) -> Statement {
+        let mut values = vec![];
+
+        while self.at() != Token::CloseSquareBracket {
+            values.push(self.parse_statement());
+
+            if self.at() != Token::CloseSquareBracket {
+                self.expect(Token::Comma, "Expected a comma between the values of the list".to_string());
+            }
+        }
+
+        self.expect(Token::CloseSquareBracket, "The list wasn't closed with a close square bracket".to_string());
+
+        Statement::List(values)
+    }
+
     fn parse_object(&mut self) -> Statement {
         self.expect(Token::OpenBracket, "The object has to be opened with an open bracket".to_string());
 
@@ -0,0 +1,4 @@
+
+pub mod frontend;
+pub mod runtime;
+pub mod simulation;
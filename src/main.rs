@@ -1,11 +1,19 @@
-#![allow(dead_code)]
+use std::env;
 
-use simulation::{update, model, view};
-
-mod frontend;
-mod runtime;
-mod simulation;
+#[cfg(feature = "window")]
+mod app;
+mod test_runner;
 
 fn main() {
-    nannou::app(model).update(update).simple_window(view).run()
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|command| command == "test") {
+        test_runner::run(&args[2..]);
+        return
+    }
+
+    #[cfg(feature = "window")]
+    nannou::app(app::model).update(app::update).exit(app::exit).simple_window(app::view).run();
+
+    #[cfg(not(feature = "window"))]
+    panic!("phyx-script was built without the 'window' feature, only 'phyx-script test' is available")
 }
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use crate::frontend::ast::Statement;
+
+use super::{eval::for_values, evaluate, Environment, Function, RuntimeValue};
+
+/// How long a coroutine waits before it's resumed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wait {
+    Seconds(f32),
+    Frames(u32)
+}
+
+/// What a coroutine did since it was started or resumed, with the object as it was at that point
+#[derive(Debug)]
+pub enum CoroutineStep {
+    Suspended(HashMap<String, RuntimeValue>),
+    Finished(HashMap<String, RuntimeValue>)
+}
+
+/// Where the coroutine is inside the function, from the function body to the innermost block
+#[derive(Debug)]
+enum Frame {
+    /// The statements of the function or of a block, blocks have their own scope
+    Block { statements: Vec<Statement>, next: usize, scoped: bool },
+    /// The values that are left for a for loop
+    For { loop_var: String, values: std::vec::IntoIter<RuntimeValue>, body: Vec<Statement> },
+    While { condition: Statement, body: Vec<Statement> }
+}
+
+/// A callback of an object that can wait. It runs statement by statement and keeps its scopes and the blocks it's in
+/// while it waits, so it can continue there on a later frame
+#[derive(Debug)]
+pub struct Coroutine {
+    parameter: String,
+    /// The innermost scope at the point where the function waits
+    scope: Environment,
+    frames: Vec<Frame>,
+    waiting: Wait
+}
+
+impl Coroutine {
+    /// Run the function with the object until it waits or finishes
+    pub fn start(func: Function, object: HashMap<String, RuntimeValue>) -> (Self, CoroutineStep) {
+        if func.parameters.len() != 1 {
+            panic!("The function '{}' takes {} arguments but only 1 is given", func.name, func.parameters.len())
+        }
+
+        let mut scope = Environment::new(func.declaration_env, true);
+        scope.declare_var(func.parameters[0].clone(), RuntimeValue::Object(object));
+
+        let mut coroutine = Self {
+            parameter: func.parameters[0].clone(),
+            scope,
+            frames: vec![Frame::Block { statements: func.body, next: 0, scoped: false }],
+            waiting: Wait::Frames(0)
+        };
+
+        let step = coroutine.run();
+        (coroutine, step)
+    }
+
+    /// Count down the wait by one frame, returns whether it should be resumed now.
+    /// Waiting for seconds ends on the frame closest to the time
+    pub fn tick(&mut self, dt: f32) -> bool {
+        match &mut self.waiting {
+            Wait::Seconds(seconds) => {
+                *seconds -= dt;
+                *seconds <= dt / 2.0
+            }
+            Wait::Frames(frames) => {
+                *frames = frames.saturating_sub(1);
+                *frames == 0
+            }
+        }
+    }
+
+    /// Continue the function with the current state of the object until it waits again or finishes
+    pub fn resume(&mut self, object: HashMap<String, RuntimeValue>) -> CoroutineStep {
+        self.scope.assign_var(self.parameter.clone(), RuntimeValue::Object(object));
+        self.run()
+    }
+
+    fn run(&mut self) -> CoroutineStep {
+        loop {
+            let Some(frame) = self.frames.last_mut() else {
+                return CoroutineStep::Finished(self.object())
+            };
+
+            match frame {
+                Frame::Block { statements, next, scoped } => {
+                    let Some(statement) = statements.get(*next).cloned() else {
+                        let scoped = *scoped;
+                        self.frames.pop();
+                        if scoped {
+                            self.leave_scope();
+                        }
+                        continue
+                    };
+                    *next += 1;
+
+                    if let Some(wait) = self.execute(statement) {
+                        self.waiting = wait;
+                        return CoroutineStep::Suspended(self.object())
+                    }
+                }
+                Frame::For { loop_var, values, body } => match values.next() {
+                    Some(value) => {
+                        let (loop_var, body) = (loop_var.clone(), body.clone());
+                        self.enter_block(body);
+                        self.scope.declare_var(loop_var, value);
+                    }
+                    None => {
+                        self.frames.pop();
+                    }
+                },
+                Frame::While { condition, body } => {
+                    let (condition, body) = (condition.clone(), body.clone());
+                    if self.condition(&condition) {
+                        self.enter_block(body);
+                    } else {
+                        self.frames.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run a statement of the function, blocks that wait are entered instead so the coroutine can stop inside them
+    fn execute(&mut self, statement: Statement) -> Option<Wait> {
+        if let Some((name, args)) = wait_call(&statement) {
+            let values: Vec<RuntimeValue> = args.iter().map(|arg| evaluate(arg.clone(), &mut self.scope)).collect();
+            return Some(match (name, values.as_slice()) {
+                ("wait", [RuntimeValue::Number(seconds)]) => Wait::Seconds(*seconds),
+                ("wait_frames", [RuntimeValue::Number(frames)]) => Wait::Frames(frames.max(0.0) as u32),
+                (name, _) => panic!("'{}' takes a number, got {:?}", name, values)
+            })
+        }
+
+        if !calls_wait(&statement) {
+            evaluate(statement, &mut self.scope);
+            return None
+        }
+
+        match statement {
+            Statement::If { condition, body, else_body } => {
+                let body = if self.condition(&condition) { body } else { else_body };
+                self.enter_block(body);
+            }
+            Statement::ForLoop { loop_var, range, body } => {
+                let values = for_values(evaluate(*range, &mut self.scope));
+                self.frames.push(Frame::For { loop_var, values: values.into_iter(), body });
+            }
+            Statement::While { condition, body } => {
+                self.frames.push(Frame::While { condition: *condition, body });
+            }
+            // 'wait' inside an expression is evaluated like any other call, which fails with a clear message
+            statement => {
+                evaluate(statement, &mut self.scope);
+            }
+        }
+
+        None
+    }
+
+    fn condition(&mut self, condition: &Statement) -> bool {
+        match evaluate(condition.clone(), &mut self.scope) {
+            RuntimeValue::Boolean(boolean) => boolean,
+            value => panic!("Value '{}' is not a boolean", value)
+        }
+    }
+
+    /// Run the statements in a new scope, like the blocks of if, for and while
+    fn enter_block(&mut self, statements: Vec<Statement>) {
+        self.scope = Environment::new(self.scope.clone(), false);
+        self.frames.push(Frame::Block { statements, next: 0, scoped: true });
+    }
+
+    /// The scope of a block is a copy of the outer one with the changes made inside, so it replaces it
+    fn leave_scope(&mut self) {
+        let parent = self.scope.parent.take().expect("The scoped environment doesn't have a parent");
+        self.scope = *parent;
+    }
+
+    fn object(&self) -> HashMap<String, RuntimeValue> {
+        match self.scope.lookup_var(self.parameter.clone()) {
+            RuntimeValue::Object(map) => map,
+            value => panic!("Invalid object: {}", value)
+        }
+    }
+}
+
+/// The name and arguments of a 'wait(seconds)' or 'wait_frames(n)' statement, also written as 'self.wait(seconds)'
+fn wait_call(statement: &Statement) -> Option<(&str, &[Statement])> {
+    let Statement::CallExpr { args, caller } = statement else {
+        return None
+    };
+
+    let name = match caller.as_ref() {
+        Statement::Identifier(name) => name,
+        Statement::MemberExpr { object, property } => match (object.as_ref(), property.as_ref()) {
+            (Statement::Identifier(_), Statement::Identifier(name)) => name,
+            _ => return None
+        },
+        _ => return None
+    };
+
+    match name.as_str() {
+        "wait" | "wait_frames" => Some((name.as_str(), args.as_slice())),
+        _ => None
+    }
+}
+
+/// Whether the function waits in its body or in the blocks inside it, only those run as coroutines
+pub fn uses_wait(func: &Function) -> bool {
+    func.body.iter().any(calls_wait)
+}
+
+fn calls_wait(statement: &Statement) -> bool {
+    match statement {
+        Statement::CallExpr { args, caller } => {
+            wait_call(statement).is_some() || calls_wait(caller) || args.iter().any(calls_wait)
+        }
+
+        Statement::Program { body } => body.iter().any(calls_wait),
+        Statement::ForLoop { range, body, .. } => calls_wait(range) || body.iter().any(calls_wait),
+        Statement::While { condition, body } => calls_wait(condition) || body.iter().any(calls_wait),
+        Statement::If { condition, body, else_body } => calls_wait(condition) || body.iter().chain(else_body).any(calls_wait),
+
+        Statement::VarDeclaration { value, .. } | Statement::GlobalDeclaration { value, .. } => calls_wait(value),
+        Statement::AssignmentExpr { assignee, value } => calls_wait(assignee) || calls_wait(value),
+        Statement::MemberExpr { object, property } => calls_wait(object) || calls_wait(property),
+
+        Statement::BinaryExpr { left, right, .. } | Statement::BooleanExpr { left, right, .. } | Statement::Comparison { left, right, .. } => {
+            calls_wait(left) || calls_wait(right)
+        }
+        Statement::UnaryExpr { value, .. } => calls_wait(value),
+
+        Statement::Object(map) => map.values().any(calls_wait),
+        Statement::List(values) => values.iter().any(calls_wait),
+
+        // Functions declared inside run when they're called, not as part of this function
+        Statement::FunctionDeclaration { .. } | Statement::Identifier(_) | Statement::NumericLiteral(_) | Statement::StringLiteral(_) => false
+    }
+}
@@ -1,22 +1,86 @@
-use std::{collections::HashMap, fmt::Debug};
-
-use nannou::color::*;
+use std::{collections::{HashMap, HashSet}, fmt::Debug, cell::RefCell, rc::Rc};
 
 use crate::{frontend::ShapeType, runtime::values::RuntimeValue};
 
-use super::native_fns;
+use super::{colors::Color, native::{IntoNativeFn, NativeFn}, native_fns, Function, Timers};
+
+/// A joint between two objects that a script asked for, the options are checked when it's added
+#[derive(Debug, Clone)]
+pub struct JointRequest {
+    pub id: u32,
+    /// The ids of the objects
+    pub first: u32,
+    pub second: u32,
+    pub options: HashMap<String, RuntimeValue>
+}
+
+/// A push that a script gave objects, applied right before the next physics step
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForceRequest {
+    /// Acts for one step
+    Force { id: u32, force: [f32; 2] },
+    Impulse { id: u32, impulse: [f32; 2] },
+    /// The point is in world coordinates
+    ImpulseAtPoint { id: u32, impulse: [f32; 2], point: [f32; 2] },
+    /// Acts for one step
+    Torque { id: u32, torque: f32 },
+    /// Pushes every object within the radius away from the center
+    Explosion { center: [f32; 2], strength: f32, radius: f32 }
+}
+
+/// Changes to the objects of the simulation that are applied once the frame is done
+#[derive(Debug, Default)]
+pub struct PendingChanges {
+    /// The objects to add with the ids they were given
+    pub spawned: Vec<(u32, HashMap<String, RuntimeValue>)>,
+    /// The ids of the objects to remove
+    pub destroyed: Vec<u32>,
+    pub joints: Vec<JointRequest>,
+    /// The ids of the joints to remove
+    pub removed_joints: Vec<u32>,
+    /// Taken separately, so the ones from 'update' are applied in the same frame
+    pub forces: Vec<ForceRequest>,
+    next_object_id: u32,
+    next_joint_id: u32
+}
+
+/// Variables declared with 'global' and the ones set by the simulation that scripts can only read
+#[derive(Debug, Default)]
+struct Globals {
+    values: HashMap<String, RuntimeValue>,
+    read_only: HashSet<String>
+}
+
+/// The property maps of the objects in the simulation, with the index of every id so scripts can look one up
+/// without going through all of them
+#[derive(Debug, Default)]
+struct World {
+    objects: Vec<HashMap<String, RuntimeValue>>,
+    indices: HashMap<u32, usize>
+}
 
 /// Store variables during runtime
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub parent: Option<Box<Self>>,
     variables: HashMap<String, RuntimeValue>,
-    pub simulation_running: bool
+    pub simulation_running: bool,
+    /// Shared by every environment of a scene
+    pending: Rc<RefCell<PendingChanges>>,
+    /// The property maps of the objects in the simulation, shared by every environment of a scene
+    world: Rc<RefCell<World>>,
+    /// Variables declared with 'global', shared by every environment of a scene so callbacks can change them
+    globals: Rc<RefCell<Globals>>,
+    timers: Rc<RefCell<Timers>>
 }
 
 impl Environment {
     pub fn new(parent: Self, simulation_running: bool) -> Self {
         Self {
+            pending: parent.pending.clone(),
+            world: parent.world.clone(),
+            globals: parent.globals.clone(),
+            timers: parent.timers.clone(),
             parent: Some(Box::new(parent)),
             variables: HashMap::new(),
             simulation_running
@@ -27,13 +91,15 @@ impl Environment {
         let mut env = Self {
             parent: None,
             variables: HashMap::new(),
-            simulation_running: false
+            simulation_running: false,
+            pending: Rc::new(RefCell::new(PendingChanges::default())),
+            world: Rc::new(RefCell::new(World::default())),
+            globals: Rc::new(RefCell::new(Globals::default())),
+            timers: Rc::new(RefCell::new(Timers::default()))
         };
 
         for (name, color) in &super::colors::COLORS {
-            env.declare_var(name.to_string(), RuntimeValue::Color(Rgb::new(
-                color.red, color.green, color.blue
-            )));
+            env.declare_var(name.to_string(), RuntimeValue::Color(*color));
         }
 
         for note in notes {
@@ -41,7 +107,8 @@ impl Environment {
         }
 
         env.declare_var("objects".to_string(), RuntimeValue::Objects(vec![]));
-        env.declare_var("background_color".to_string(), RuntimeValue::Color(BLACK));
+        env.declare_var("world".to_string(), RuntimeValue::World);
+        env.declare_var("background_color".to_string(), RuntimeValue::Color(Color::new(0, 0, 0)));
 
         env.declare_var("true".to_string(), RuntimeValue::Boolean(true));
         env.declare_var("false".to_string(), RuntimeValue::Boolean(false));
@@ -49,24 +116,191 @@ impl Environment {
         env.declare_var("circle".to_string(), RuntimeValue::Shape(ShapeType::Circle));
         env.declare_var("rect".to_string(), RuntimeValue::Shape(ShapeType::Rect));
         env.declare_var("ring".to_string(), RuntimeValue::Shape(ShapeType::Ring));
+        env.declare_var("arc".to_string(), RuntimeValue::Shape(ShapeType::Arc));
+        env.declare_var("capsule".to_string(), RuntimeValue::Shape(ShapeType::Capsule));
+        env.declare_var("triangle".to_string(), RuntimeValue::Shape(ShapeType::Triangle));
+        env.declare_var("rounded_rect".to_string(), RuntimeValue::Shape(ShapeType::RoundedRect));
+        env.declare_var("box".to_string(), RuntimeValue::Shape(ShapeType::HollowRect));
+        env.declare_native("polygon", "A shape from a list of [x, y] points scaled by the width and height, like [1, 1] for the top right corner of a rect", native_fns::polygon);
+        env.declare_native("ngon", "A regular polygon shape with the number of sides", native_fns::ngon);
+        env.declare_native("hollow", "Only the outline of a rect, circle or polygon, as thick as the stroke weight", native_fns::hollow);
+        env.declare_native("line", "A wall from one [x, y] point to another, in world coordinates when the object is at the origin", native_fns::line);
+        env.declare_native("polyline", "A wall through a list of [x, y] points, in world coordinates when the object is at the origin", native_fns::polyline);
+
+        env.declare_var("print".to_string(), RuntimeValue::NativeFn(NativeFn::variadic("print", "Print all the arguments separated by spaces", native_fns::print)));
+        env.declare_native("help", "Get the signature and documentation of a function", native_fns::help);
+
+        env.declare_native("rgb", "Create a color from red, green and blue values between 0 and 255", native_fns::rgb);
+        env.declare_native("hsv", "Create a color from a hue between 0 and 360 and a saturation and value between 0 and 1", native_fns::hsv);
+
+        env.declare_native("range", "range(stop), range(start, stop) or range(start, stop, step) to loop over", native_fns::range);
+        env.declare_native("random", "random() between 0 and 1, random(stop) between 0 and stop or random(start, stop)", native_fns::random);
+        env.declare_native("floor", "Round a number down", native_fns::floor);
+        env.declare_native("ceil", "Round a number up", native_fns::ceil);
+
+        env.declare_native("abs", "The absolute value of a number", native_fns::abs);
+        env.declare_native("pow", "Raise the base to the power of the exponent", native_fns::pow);
+        env.declare_native("sqrt", "The square root of a number", native_fns::sqrt);
+
+        env.declare_native("sin", "The sine of an angle in radians", native_fns::sin);
+        env.declare_native("cos", "The cosine of an angle in radians", native_fns::cos);
+
+        env.declare_native("spawn", "Add an object to the simulation at the end of the frame, returns its id", native_fns::spawn);
+        env.declare_native("destroy", "Remove an object from the simulation before the next frame", native_fns::destroy);
+
+        env.declare_native("joint", "Connect two objects or ids with a fixed, revolute or prismatic joint, returns the joint", native_fns::joint);
+        env.declare_native("spring", "Connect two objects or ids with a spring of their current distance, returns the joint", native_fns::spring);
+        env.declare_native("rope", "Keep two objects or ids at most the length apart, returns the joint", native_fns::rope);
+        env.declare_native("break_joint", "Remove a joint before the next frame", native_fns::break_joint);
+
+        env.declare_native("apply_force", "Push an object or id with a force for one frame", native_fns::apply_force);
+        env.declare_native("apply_impulse", "Change the momentum of an object or id at once", native_fns::apply_impulse);
+        env.declare_native("apply_impulse_at_point", "Change the momentum of an object or id at a point in world coordinates, which can also turn it", native_fns::apply_impulse_at_point);
+        env.declare_native("apply_torque", "Turn an object or id with a torque for one frame", native_fns::apply_torque);
+        env.declare_native("explode", "Push all objects within the radius away from a point, with the strength as the speed at the center", native_fns::explode);
+
+        env.declare_native("after", "Call the function once after some seconds, returns the timer", native_fns::after);
+        env.declare_native("every", "Call the function every some seconds, returns the timer", native_fns::every);
+        env.declare_native("cancel", "Stop a timer from calling its function", native_fns::cancel);
+
+        env.declare_native("wait", "Pause the 'update' function for some seconds, the object keeps moving meanwhile", native_fns::wait);
+        env.declare_native("wait_frames", "Pause the 'update' function for some frames, the object keeps moving meanwhile", native_fns::wait_frames);
+
+        env.declare_native("len", "The number of values in a list, properties of an object or characters of a string", native_fns::len);
+        env.declare_native("keys", "A sorted list of the property names of an object", native_fns::keys);
+        env.declare_native("has", "Whether the object has the property", native_fns::has);
+        env.declare_native("get", "The value of the property or the default if the object doesn't have it", native_fns::get);
+        env.declare_native("remove", "A copy of the object without the property", native_fns::remove);
+        env.declare_native("type_of", "The name of the type of a value", native_fns::type_of);
+
+        env.declare_native("assert", "Fail the test with the message if the condition is false", native_fns::assert);
+        env.declare_native("assert_eq", "Fail the test if the values aren't equal", native_fns::assert_eq);
 
-        env.declare_var("print".to_string(), RuntimeValue::NativeFn(native_fns::print));
-        env.declare_var("rgb".to_string(), RuntimeValue::NativeFn(native_fns::rgb));
-        env.declare_var("hsv".to_string(), RuntimeValue::NativeFn(native_fns::hsv));
+        env
+    }
 
-        env.declare_var("range".to_string(), RuntimeValue::NativeFn(native_fns::range));
-        env.declare_var("random".to_string(), RuntimeValue::NativeFn(native_fns::random));
-        env.declare_var("floor".to_string(), RuntimeValue::NativeFn(native_fns::floor));
-        env.declare_var("ceil".to_string(), RuntimeValue::NativeFn(native_fns::ceil));
+    /// Declare a Rust function as a native function, its arguments are checked using the types of its parameters
+    pub fn declare_native<Args>(&mut self, name: &str, doc: &str, func: impl IntoNativeFn<Args>) -> RuntimeValue {
+        self.declare_var(name.to_string(), RuntimeValue::NativeFn(NativeFn::new(name, doc, func)))
+    }
 
-        env.declare_var("abs".to_string(), RuntimeValue::NativeFn(native_fns::abs));
-        env.declare_var("pow".to_string(), RuntimeValue::NativeFn(native_fns::pow));
-        env.declare_var("sqrt".to_string(), RuntimeValue::NativeFn(native_fns::sqrt));
+    /// Queue an object to be added to the simulation, returns the id it will have
+    pub fn spawn(&self, object: HashMap<String, RuntimeValue>) -> u32 {
+        let mut pending = self.pending.borrow_mut();
 
-        env.declare_var("sin".to_string(), RuntimeValue::NativeFn(native_fns::sin));
-        env.declare_var("cos".to_string(), RuntimeValue::NativeFn(native_fns::cos));
+        let id = pending.next_object_id;
+        pending.next_object_id += 1;
+        pending.spawned.push((id, object));
 
-        env
+        id
+    }
+
+    /// Reserve an id for an object that is added to the simulation right away
+    pub fn next_object_id(&self) -> u32 {
+        let mut pending = self.pending.borrow_mut();
+
+        pending.next_object_id += 1;
+        pending.next_object_id - 1
+    }
+
+    /// Queue a joint between the objects with the ids, returns the id of the joint
+    pub fn add_joint(&self, first: u32, second: u32, options: HashMap<String, RuntimeValue>) -> u32 {
+        let mut pending = self.pending.borrow_mut();
+
+        let id = pending.next_joint_id;
+        pending.next_joint_id += 1;
+        pending.joints.push(JointRequest { id, first, second, options });
+
+        id
+    }
+
+    /// Queue the joint with the id to be removed from the simulation
+    pub fn remove_joint(&self, id: u32) {
+        self.pending.borrow_mut().removed_joints.push(id);
+    }
+
+    /// Queue the object with the id to be removed from the simulation
+    pub fn destroy(&self, id: u32) {
+        self.pending.borrow_mut().destroyed.push(id);
+    }
+
+    /// Queue a push for the next physics step
+    pub fn push(&self, force: ForceRequest) {
+        self.pending.borrow_mut().forces.push(force);
+    }
+
+    /// Take the forces queued since the last call
+    pub fn take_forces(&self) -> Vec<ForceRequest> {
+        std::mem::take(&mut self.pending.borrow_mut().forces)
+    }
+
+    /// Take all the changes queued since the last call except the forces, ids keep counting up
+    pub fn take_pending(&self) -> PendingChanges {
+        let mut pending = self.pending.borrow_mut();
+
+        PendingChanges {
+            spawned: std::mem::take(&mut pending.spawned),
+            destroyed: std::mem::take(&mut pending.destroyed),
+            joints: std::mem::take(&mut pending.joints),
+            removed_joints: std::mem::take(&mut pending.removed_joints),
+            ..PendingChanges::default()
+        }
+    }
+
+    /// Call the function after the delay in seconds of simulation time, and then every interval if there is one
+    pub fn schedule(&self, delay: f32, interval: Option<f32>, func: Function) -> u32 {
+        self.timers.borrow_mut().schedule(delay, interval, func)
+    }
+
+    pub fn cancel_timer(&self, id: u32) {
+        self.timers.borrow_mut().cancel(id);
+    }
+
+    /// Move the timers to the time of a new frame and take the functions that are due
+    pub fn due_timers(&self, time: f32, dt: f32) -> Vec<Function> {
+        self.timers.borrow_mut().advance(time, dt)
+    }
+
+    /// The property maps of the objects in the simulation
+    pub fn world_objects(&self) -> Vec<HashMap<String, RuntimeValue>> {
+        self.world.borrow().objects.clone()
+    }
+
+    /// The number of objects in the simulation
+    pub fn world_len(&self) -> usize {
+        self.world.borrow().objects.len()
+    }
+
+    /// The property map of the object with the id, if it's in the simulation
+    pub fn world_object(&self, id: u32) -> Option<HashMap<String, RuntimeValue>> {
+        let world = self.world.borrow();
+        world.indices.get(&id).map(|&index| world.objects[index].clone())
+    }
+
+    /// The property maps of the objects with the tag
+    pub fn world_find(&self, tag: &str) -> Vec<HashMap<String, RuntimeValue>> {
+        let tag = RuntimeValue::String(tag.to_string());
+        self.world.borrow().objects.iter()
+            .filter(|object| matches!(object.get("tags"), Some(RuntimeValue::List(tags)) if tags.contains(&tag)))
+            .cloned()
+            .collect()
+    }
+
+    /// Replace the property maps of all the objects in the simulation
+    pub fn set_world_objects(&self, objects: Vec<HashMap<String, RuntimeValue>>) {
+        let indices = objects.iter().enumerate()
+            .filter_map(|(index, object)| match object.get("id") {
+                Some(RuntimeValue::Number(id)) => Some((*id as u32, index)),
+                _ => None
+            })
+            .collect();
+
+        *self.world.borrow_mut() = World { objects, indices };
+    }
+
+    /// Replace the property map of the object at the index after one of its callbacks changed it
+    pub fn set_world_object(&self, index: usize, object: HashMap<String, RuntimeValue>) {
+        self.world.borrow_mut().objects[index] = object;
     }
 
     pub fn declare_var(&mut self, varname: String, value: RuntimeValue) -> RuntimeValue {
@@ -78,7 +312,58 @@ impl Environment {
         value
     }
 
+    /// Declare a variable that every callback sees and can change, changes are visible right away
+    pub fn declare_global(&mut self, varname: String, value: RuntimeValue) -> RuntimeValue {
+        if self.declares(&varname) {
+            panic!("Cannot declare global '{:?}' as it's already defined", varname)
+        }
+
+        let mut globals = self.globals.borrow_mut();
+        if globals.values.contains_key(&varname) {
+            panic!("Cannot declare global '{:?}' as it's already defined", varname)
+        }
+
+        globals.values.insert(varname, value.clone());
+        value
+    }
+
+    /// Set a global that scripts can read but not change
+    pub fn set_read_only(&self, varname: &str, value: RuntimeValue) {
+        let mut globals = self.globals.borrow_mut();
+
+        globals.values.insert(varname.to_string(), value);
+        globals.read_only.insert(varname.to_string());
+    }
+
+    /// Whether this environment or one of its parents declares the variable, ignoring globals
+    fn declares(&self, varname: &String) -> bool {
+        self.variables.contains_key(varname) || self.parent.as_ref().is_some_and(|parent| parent.declares(varname))
+    }
+
+    fn global(&self, varname: &String) -> Option<RuntimeValue> {
+        self.globals.borrow().values.get(varname).cloned()
+    }
+
+    /// Whether the environment is part of a callback of the running simulation
+    pub fn in_simulation(&self) -> bool {
+        self.simulation_running || self.parent.as_ref().is_some_and(|parent| parent.in_simulation())
+    }
+
     pub fn assign_var(&mut self, varname: String, value: RuntimeValue) -> RuntimeValue {
+        // Variables declared with 'let' shadow globals
+        if !self.declares(&varname) {
+            let mut globals = self.globals.borrow_mut();
+
+            if globals.read_only.contains(&varname) {
+                panic!("Cannot assign to '{}' because it's read-only", varname)
+            }
+
+            if let Some(global) = globals.values.get_mut(&varname) {
+                *global = value.clone();
+                return value
+            }
+        }
+
         let env = self.resolve_mut(&varname);
         env.variables.insert(varname, value.clone());
 
@@ -86,6 +371,12 @@ impl Environment {
     }
 
     pub fn lookup_var(&self, varname: String) -> RuntimeValue {
+        if !self.declares(&varname) {
+            if let Some(value) = self.global(&varname) {
+                return value
+            }
+        }
+
         let env = self.resolve(&varname);
 
         env.variables.get(&varname).expect("'resolve' succeeded but varname is not present").clone()
@@ -1,6 +1,6 @@
 use std::borrow::Borrow;
 
-use crate::{frontend::ast::Statement, runtime::{evaluate, values::Function, Environment, RuntimeValue}};
+use crate::{frontend::ast::Statement, runtime::{evaluate, native_fns, values::Function, Environment, NativeFn, RuntimeValue}};
 
 pub fn eval_numeric_binary_expr(left: f32, right: f32, operator: String) -> RuntimeValue {
     match operator.as_str() {
@@ -73,6 +73,8 @@ pub fn eval_comparison_expr(left: &Statement, right: &Statement, operator: Strin
         (RuntimeValue::Boolean(left_val), RuntimeValue::Boolean(right_val)) => eval_other_comparison_expr(left_val, right_val, operator),
         (RuntimeValue::Color(left_val), RuntimeValue::Color(right_val)) => eval_other_comparison_expr(left_val, right_val, operator),
         (RuntimeValue::Shape(left_val), RuntimeValue::Shape(right_val)) => eval_other_comparison_expr(left_val, right_val, operator),
+        (RuntimeValue::String(left_val), RuntimeValue::String(right_val)) => eval_other_comparison_expr(left_val, right_val, operator),
+        (RuntimeValue::Note(left_val), RuntimeValue::Note(right_val)) => eval_other_comparison_expr(left_val, right_val, operator),
         _ => panic!("Invalid comparison: {:?} to {:?}", left_eval, right_eval)
     }
 }
@@ -135,13 +137,25 @@ pub fn eval_assignment(assignee: &Statement, value: &Statement, env: &mut Enviro
 }
 
 pub fn eval_call_expr(args: Vec<Statement>, caller: &Statement, env: &mut Environment) -> RuntimeValue {
+    // 'object.function(args)' is the same as 'function(object, args)' when the object doesn't have that property
+    if let Statement::MemberExpr { object, property } = caller {
+        if let (Statement::Identifier(object_name), Statement::Identifier(property_name)) = (object.borrow(), property.borrow()) {
+            if let RuntimeValue::Object(map) = env.lookup_var(object_name.clone()) {
+                if !map.contains_key(property_name) {
+                    let args = std::iter::once(object.as_ref().clone()).chain(args).collect();
+                    return eval_call_expr(args, property, env);
+                }
+            }
+        }
+    }
+
     let mut values = vec![];
     for arg in args.clone() {
         values.push(evaluate(arg, env));
     }
 
     match evaluate(caller.clone(), env) {
-        RuntimeValue::NativeFn(func) => func(values, env),
+        RuntimeValue::NativeFn(func) => func.call(values, env),
         RuntimeValue::Function(Function { name, parameters, body, declaration_env }) => {
             let mut scope = Environment::new(declaration_env, false);
 
@@ -161,12 +175,15 @@ pub fn eval_call_expr(args: Vec<Statement>, caller: &Statement, env: &mut Enviro
                 result = evaluate(statement, &mut scope);
             }
 
-            let mut top_env = scope.clone();
-            while let Some(env) = top_env.parent {
-                top_env = *env;
-            }
+            // Callbacks can't change the variables outside of them, only globals, which are already shared
+            if !env.in_simulation() {
+                let mut top_env = scope.clone();
+                while let Some(env) = top_env.parent {
+                    top_env = *env;
+                }
 
-            env.merge_objects(top_env);
+                env.merge_objects(top_env);
+            }
 
             result
         }
@@ -178,10 +195,18 @@ pub fn eval_call_expr(args: Vec<Statement>, caller: &Statement, env: &mut Enviro
     }
 }
 
-pub fn eval_runtime_object_expr(object: RuntimeValue, func: &mut Function) -> RuntimeValue {
+/// Call a callback of an object, the parameters after 'self' get the extra arguments and can be left out
+pub fn eval_runtime_object_expr(object: RuntimeValue, args: Vec<RuntimeValue>, func: &mut Function) -> RuntimeValue {
     let mut scope = Environment::new(func.declaration_env.clone(), true);
 
+    if func.parameters.len() > args.len() + 1 {
+        panic!("The function '{}' takes {} arguments but only {} are given", func.name, func.parameters.len(), args.len() + 1)
+    }
+
     scope.declare_var(func.parameters[0].clone(), object.clone());
+    for (parameter, value) in func.parameters[1..].iter().zip(args) {
+        scope.declare_var(parameter.clone(), value);
+    }
 
     for statement in func.body.clone() {
         evaluate(statement, &mut scope);
@@ -214,6 +239,19 @@ pub fn eval_member_expr(object: &Statement, property: &Statement, env: &mut Envi
             None => panic!("Object '{:?}' doesn't have property '{:?}'", object_name, property_name)
         }
         RuntimeValue::Objects(objects) => RuntimeValue::Objects(objects),
+        RuntimeValue::World => world_property(property_name, env),
         value => panic!("Invalid runtime value: '{:?}'", value)
     }
 }
+
+/// The properties of 'world', which always show the current state of the simulation
+fn world_property(property_name: &str, env: &mut Environment) -> RuntimeValue {
+    match property_name {
+        "objects" => RuntimeValue::List(env.world_objects().into_iter().map(RuntimeValue::Object).collect()),
+        "count" => RuntimeValue::NativeFn(NativeFn::new("world.count", "The number of objects in the simulation", native_fns::world_count)),
+        "find" => RuntimeValue::NativeFn(NativeFn::new("world.find", "All the objects that have the tag", native_fns::world_find)),
+        "get" => RuntimeValue::NativeFn(NativeFn::new("world.get", "The object with the id", native_fns::world_get)),
+        "has" => RuntimeValue::NativeFn(NativeFn::new("world.has", "Whether an object with the id is in the simulation", native_fns::world_has)),
+        property => panic!("'world' doesn't have property '{}'", property)
+    }
+}
@@ -17,23 +17,40 @@ pub fn eval_var_declaration(identifier: String, value: &Statement, env: &mut Env
     env.declare_var(identifier, value)
 }
 
+pub fn eval_global_declaration(identifier: String, value: &Statement, env: &mut Environment) -> RuntimeValue {
+    let value = evaluate(value.clone(), env);
+    env.declare_global(identifier, value)
+}
+
 pub fn eval_function_declaration(name: String, parameters: Vec<String>, body: Vec<Statement>, env: &mut Environment) -> RuntimeValue {
     let func = RuntimeValue::Function(Function::new(name.clone(), parameters, body, env.clone()));
 
     env.declare_var(name, func)
 }
 
+/// The scope of an if, for or while block. Only the scope of a callback stops assignments from reaching outer variables,
+/// so a block in 'update' can change 'self' and the variables of the callback but not the ones declared outside of it
+fn block_scope(env: &Environment) -> Environment {
+    Environment::new(env.clone(), false)
+}
+
+/// The values a for loop goes through
+pub fn for_values(range: RuntimeValue) -> Vec<RuntimeValue> {
+    match range {
+        RuntimeValue::Range(start, stop, step) => (start..stop).step_by(step).map(|i| RuntimeValue::Number(i as f32)).collect(),
+        RuntimeValue::List(values) => values,
+        value => panic!("Expected a range or list, got: {:?}", value)
+    }
+}
+
 pub fn eval_for_loop(loop_var: String, range: &Statement, body: Vec<Statement>, env: &mut Environment) -> RuntimeValue {
-    let (start, stop, step) = match evaluate(range.clone(), env) {
-        RuntimeValue::Range(start, stop, step) => (start, stop, step),
-        _ => panic!("Expected a range")
-    };
+    let values = for_values(evaluate(range.clone(), env));
 
     let mut result = RuntimeValue::Number(0.0);
 
-    for i in (start..stop).step_by(step) {
-        let mut scope = Environment::new(env.clone(), env.simulation_running);
-        scope.declare_var(loop_var.clone(), RuntimeValue::Number(i as f32));
+    for value in values {
+        let mut scope = block_scope(env);
+        scope.declare_var(loop_var.clone(), value);
 
         for statement in body.clone() {
             result = evaluate(statement, &mut scope);
@@ -53,7 +70,7 @@ pub fn eval_if_statement(condition: &Statement, body: Vec<Statement>, else_body:
     };
 
     let mut result = RuntimeValue::Number(0.0);
-    let mut scope = Environment::new(env.clone(), env.simulation_running);
+    let mut scope = block_scope(env);
     if boolean {
         for statement in body.clone() {
             result = evaluate(statement, &mut scope);
@@ -78,7 +95,7 @@ pub fn eval_while_statement(condition: &Statement, body: Vec<Statement>, env: &m
 
     let mut result = RuntimeValue::Number(0.0);
     while boolean {
-        let mut scope = Environment::new(env.clone(), env.simulation_running);
+        let mut scope = block_scope(env);
 
         for statement in body.clone() {
             result = evaluate(statement, &mut scope);
@@ -104,3 +121,7 @@ pub fn eval_object(map: HashMap<String, Statement>, env: &mut Environment) -> Ru
 
     RuntimeValue::Object(var_map)
 }
+
+pub fn eval_list(values: Vec<Statement>, env: &mut Environment) -> RuntimeValue {
+    RuntimeValue::List(values.into_iter().map(|value| evaluate(value, env)).collect())
+}
@@ -1,6 +1,6 @@
 use crate::frontend::ast::Statement;
 
-use super::{environment::Environment, eval::{eval_assignment, eval_binary_expr, eval_boolean_expr, eval_call_expr, eval_comparison_expr, eval_for_loop, eval_function_declaration, eval_identifier, eval_if_statement, eval_member_expr, eval_object, eval_program, eval_unary_expr, eval_var_declaration, eval_while_statement}, values::RuntimeValue};
+use super::{environment::Environment, eval::{eval_assignment, eval_binary_expr, eval_boolean_expr, eval_call_expr, eval_comparison_expr, eval_for_loop, eval_function_declaration, eval_global_declaration, eval_identifier, eval_if_statement, eval_list, eval_member_expr, eval_object, eval_program, eval_unary_expr, eval_var_declaration, eval_while_statement}, values::RuntimeValue};
 
 
 /// Evaluate the passed in statement
@@ -9,14 +9,17 @@ pub fn evaluate(ast_node: Statement, env: &mut Environment) -> RuntimeValue {
         Statement::Program { body } => eval_program(body, env),
 
         Statement::VarDeclaration { identifier, value } => eval_var_declaration(identifier, value.as_ref(), env),
+        Statement::GlobalDeclaration { identifier, value } => eval_global_declaration(identifier, value.as_ref(), env),
         Statement::FunctionDeclaration { name, parameters, body } => eval_function_declaration(name, parameters, body, env),
         Statement::ForLoop { loop_var, range, body } => eval_for_loop(loop_var, &range, body, env),
         Statement::If { condition, body, else_body } => eval_if_statement(&condition, body, else_body, env),
         Statement::While { condition, body } => eval_while_statement(&condition, body, env),
         Statement::Object(map) => eval_object(map, env),
+        Statement::List(values) => eval_list(values, env),
 
         Statement::AssignmentExpr { assignee, value } => eval_assignment(&assignee, &value, env),
         Statement::NumericLiteral(value) => RuntimeValue::Number(value),
+        Statement::StringLiteral(value) => RuntimeValue::String(value),
         Statement::Identifier(symbol) => eval_identifier(symbol, env),
 
         Statement::BinaryExpr { left, right, operator } => eval_binary_expr(&left, &right, operator, env),
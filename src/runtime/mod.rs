@@ -1,11 +1,18 @@
 mod colors;
+mod coroutine;
 mod eval;
 mod environment;
 mod interpreter;
+mod native;
 mod native_fns;
+mod timers;
 mod values;
 
-pub use environment::Environment;
+pub use colors::Color;
+pub use coroutine::{uses_wait, Coroutine, CoroutineStep, Wait};
+pub use environment::{Environment, ForceRequest, JointRequest, PendingChanges};
 pub use interpreter::evaluate;
+pub use native::{FromRuntimeValue, IntoNativeFn, IntoRuntimeValue, NativeFn};
+pub use timers::Timers;
 pub use values::{RuntimeValue, Function};
 pub(crate) use eval::eval_runtime_object_expr;
\ No newline at end of file
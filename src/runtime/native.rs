@@ -0,0 +1,245 @@
+use std::{collections::HashMap, fmt::Debug, rc::Rc};
+
+use crate::frontend::ShapeType;
+
+use super::{Color, Environment, Function, RuntimeValue};
+
+type NativeFnPtr = dyn Fn(Vec<RuntimeValue>, &mut Environment) -> RuntimeValue;
+
+/// A function implemented in Rust that can be called from a script
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: String,
+    pub doc: String,
+    parameters: Vec<String>,
+    return_type: String,
+    func: Rc<NativeFnPtr>
+}
+
+impl NativeFn {
+    /// Create a native function from a Rust function with typed parameters, the arguments are checked before calling it
+    pub fn new<Args>(name: &str, doc: &str, func: impl IntoNativeFn<Args>) -> Self {
+        func.into_native_fn(name.to_string(), doc.to_string())
+    }
+
+    /// Create a native function that takes any number of arguments of any type
+    pub fn variadic<F>(name: &str, doc: &str, func: F) -> Self
+        where F: Fn(Vec<RuntimeValue>, &mut Environment) -> RuntimeValue + 'static {
+        Self {
+            name: name.to_string(),
+            doc: doc.to_string(),
+            parameters: vec!["any...".to_string()],
+            return_type: "any".to_string(),
+            func: Rc::new(func)
+        }
+    }
+
+    pub fn call(&self, args: Vec<RuntimeValue>, env: &mut Environment) -> RuntimeValue {
+        (self.func)(args, env)
+    }
+
+    /// The signature and documentation of the function
+    pub fn help(&self) -> String {
+        format!("{}({}) -> {}\n    {}", self.name, self.parameters.join(", "), self.return_type, self.doc)
+    }
+}
+
+impl Debug for NativeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({}) -> {}", self.name, self.parameters.join(", "), self.return_type)
+    }
+}
+
+/// A type that can be the parameter of a native function
+pub trait FromRuntimeValue: Sized {
+    fn type_name() -> String;
+
+    fn from_value(value: RuntimeValue) -> Option<Self>;
+
+    /// The value to use when the argument isn't given, None if it's required
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// A type that can be returned from a native function
+pub trait IntoRuntimeValue {
+    fn type_name() -> String;
+
+    fn into_value(self) -> RuntimeValue;
+}
+
+macro_rules! runtime_value_conversion {
+    ($type:ty, $name:literal, $variant:ident) => {
+        impl FromRuntimeValue for $type {
+            fn type_name() -> String {
+                $name.to_string()
+            }
+
+            fn from_value(value: RuntimeValue) -> Option<Self> {
+                match value {
+                    RuntimeValue::$variant(value) => Some(value),
+                    _ => None
+                }
+            }
+        }
+
+        impl IntoRuntimeValue for $type {
+            fn type_name() -> String {
+                $name.to_string()
+            }
+
+            fn into_value(self) -> RuntimeValue {
+                RuntimeValue::$variant(self)
+            }
+        }
+    };
+}
+
+runtime_value_conversion!(f32, "number", Number);
+runtime_value_conversion!(bool, "boolean", Boolean);
+runtime_value_conversion!(String, "string", String);
+runtime_value_conversion!(Color, "color", Color);
+runtime_value_conversion!(ShapeType, "shape", Shape);
+runtime_value_conversion!(HashMap<String, RuntimeValue>, "object", Object);
+runtime_value_conversion!(Vec<RuntimeValue>, "list", List);
+runtime_value_conversion!(Function, "function", Function);
+
+impl FromRuntimeValue for RuntimeValue {
+    fn type_name() -> String {
+        "any".to_string()
+    }
+
+    fn from_value(value: RuntimeValue) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl IntoRuntimeValue for RuntimeValue {
+    fn type_name() -> String {
+        "any".to_string()
+    }
+
+    fn into_value(self) -> RuntimeValue {
+        self
+    }
+}
+
+impl IntoRuntimeValue for () {
+    fn type_name() -> String {
+        "nothing".to_string()
+    }
+
+    fn into_value(self) -> RuntimeValue {
+        RuntimeValue::Number(0.0)
+    }
+}
+
+impl<T: FromRuntimeValue> FromRuntimeValue for Option<T> {
+    fn type_name() -> String {
+        T::type_name() + "?"
+    }
+
+    fn from_value(value: RuntimeValue) -> Option<Self> {
+        T::from_value(value).map(Some)
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+/// Get the argument at the index converted to the type of the parameter, panics with a helpful message if it's invalid
+pub fn argument<T: FromRuntimeValue>(name: &str, args: &mut std::vec::IntoIter<RuntimeValue>, index: usize) -> T {
+    match args.next() {
+        Some(value) => match T::from_value(value.clone()) {
+            Some(value) => value,
+            None => panic!("Invalid argument {} to '{}': expected {}, got {} ({})", index + 1, name, T::type_name(), value.type_name(), value)
+        },
+        None => match T::from_missing() {
+            Some(value) => value,
+            None => panic!("Missing argument {} to '{}': expected {}", index + 1, name, T::type_name())
+        }
+    }
+}
+
+/// A Rust function that can be turned into a native function, 'Args' are the types of its parameters
+pub trait IntoNativeFn<Args> {
+    fn into_native_fn(self, name: String, doc: String) -> NativeFn;
+}
+
+/// Marker for native functions that take the environment as their first parameter
+pub struct WithEnv<Args>(std::marker::PhantomData<Args>);
+
+macro_rules! into_native_fn {
+    ($($param:ident),*) => {
+        impl<Func, Ret, $($param),*> IntoNativeFn<($($param,)*)> for Func
+            where Func: Fn($($param),*) -> Ret + 'static, Ret: IntoRuntimeValue, $($param: FromRuntimeValue),* {
+            #[allow(non_snake_case, unused_mut, unused_variables, unused_assignments)]
+            fn into_native_fn(self, name: String, doc: String) -> NativeFn {
+                let parameters = vec![$($param::type_name()),*];
+                let num_params = parameters.len();
+                let fn_name = name.clone();
+
+                NativeFn {
+                    name,
+                    doc,
+                    parameters,
+                    return_type: Ret::type_name(),
+                    func: Rc::new(move |args, _env| {
+                        if args.len() > num_params {
+                            panic!("'{}' takes at most {} arguments but {} were given", fn_name, num_params, args.len())
+                        }
+
+                        let mut args = args.into_iter();
+                        let mut index = 0;
+                        $(
+                            let $param: $param = argument(&fn_name, &mut args, index);
+                            index += 1;
+                        )*
+
+                        self($($param),*).into_value()
+                    })
+                }
+            }
+        }
+
+        impl<Func, Ret, $($param),*> IntoNativeFn<WithEnv<($($param,)*)>> for Func
+            where Func: Fn(&mut Environment, $($param),*) -> Ret + 'static, Ret: IntoRuntimeValue, $($param: FromRuntimeValue),* {
+            #[allow(non_snake_case, unused_mut, unused_variables, unused_assignments)]
+            fn into_native_fn(self, name: String, doc: String) -> NativeFn {
+                let parameters = vec![$($param::type_name()),*];
+                let num_params = parameters.len();
+                let fn_name = name.clone();
+
+                NativeFn {
+                    name,
+                    doc,
+                    parameters,
+                    return_type: Ret::type_name(),
+                    func: Rc::new(move |args, env| {
+                        if args.len() > num_params {
+                            panic!("'{}' takes at most {} arguments but {} were given", fn_name, num_params, args.len())
+                        }
+
+                        let mut args = args.into_iter();
+                        let mut index = 0;
+                        $(
+                            let $param: $param = argument(&fn_name, &mut args, index);
+                            index += 1;
+                        )*
+
+                        self(env, $($param),*).into_value()
+                    })
+                }
+            }
+        }
+    };
+}
+
+into_native_fn!();
+into_native_fn!(A);
+into_native_fn!(A, B);
+into_native_fn!(A, B, C);
+into_native_fn!(A, B, C, D);
+into_native_fn!(A, B, C, D, E);
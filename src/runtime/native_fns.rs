@@ -1,8 +1,10 @@
-use nannou::color;
+use std::{collections::HashMap, fmt::Write};
+
 use rand::Rng;
-use std::fmt::Write;
 
-use super::{Environment, RuntimeValue};
+use crate::frontend::ShapeType;
+
+use super::{Color, Environment, ForceRequest, Function, RuntimeValue};
 
 pub fn print(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
     let joined: String = args.iter().fold(String::new(), |mut output, arg| {
@@ -14,212 +16,314 @@ pub fn print(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
     RuntimeValue::Number(0.0)
 }
 
-pub fn rgb(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    match args.len() {
-        len if len < 3 => panic!("Too few arguments passed into 'rgb'"),
-        len if len > 3 => panic!("Too many arguments passed into 'rgb'"),
-        _ => {
-            let r = match &args[0] {
-                RuntimeValue::Number(number) => *number as u8,
-                value => panic!("Invalid argument to 'rgb': {:?}", value)
-            };
+pub fn help(value: RuntimeValue) -> String {
+    match value {
+        RuntimeValue::NativeFn(func) => func.help(),
+        RuntimeValue::Function(func) => format!("{}({})", func.name, func.parameters.join(", ")),
+        value => panic!("Invalid argument to 'help': {} is not a function", value)
+    }
+}
 
-            let g = match &args[1] {
-                RuntimeValue::Number(number) => *number as u8,
-                value => panic!("Invalid argument to 'rgb': {:?}", value)
-            };
+pub fn rgb(r: f32, g: f32, b: f32) -> Color {
+    Color::new(r as u8, g as u8, b as u8)
+}
 
-            let b = match &args[2] {
-                RuntimeValue::Number(number) => *number as u8,
-                value => panic!("Invalid argument to 'rgb': {:?}", value)
-            };
+pub fn hsv(hue: f32, saturation: f32, value: f32) -> Color {
+    let (r, g, b) = hsv::hsv_to_rgb(hue as f64, saturation as f64, value as f64);
+    Color::new(r, g, b)
+}
 
-            RuntimeValue::Color(nannou::color::rgb(r, g, b))
-        },
+pub fn range(first: f32, stop: Option<f32>, step: Option<f32>) -> RuntimeValue {
+    match stop {
+        Some(stop) => RuntimeValue::Range(first as i32, stop as i32, step.unwrap_or(1.0) as usize),
+        None => RuntimeValue::Range(0, first as i32, 1)
     }
 }
 
-pub fn hsv(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    match args.len() {
-        len if len < 3 => panic!("Too few arguments passed into 'rgb'"),
-        len if len > 3 => panic!("Too many arguments passed into 'rgb'"),
-        _ => {
-            let hue = match &args[0] {
-                RuntimeValue::Number(number) => *number as f64,
-                value => panic!("Invalid argument to 'rgb': {:?}", value)
-            };
+pub fn random(first: Option<f32>, stop: Option<f32>) -> f32 {
+    match (first, stop) {
+        (None, _) => rand::thread_rng().gen(),
+        (Some(stop), None) => rand::thread_rng().gen_range(0.0..stop),
+        (Some(start), Some(stop)) => rand::thread_rng().gen_range(start..stop)
+    }
+}
 
-            let saturation = match &args[1] {
-                RuntimeValue::Number(number) => *number as f64,
-                value => panic!("Invalid argument to 'rgb': {:?}", value)
-            };
+pub fn floor(number: f32) -> f32 {
+    number.floor()
+}
 
-            let value = match &args[2] {
-                RuntimeValue::Number(number) => *number as f64,
-                value => panic!("Invalid argument to 'rgb': {:?}", value)
-            };
+pub fn ceil(number: f32) -> f32 {
+    number.ceil()
+}
 
-            let (r, g, b) = hsv::hsv_to_rgb(hue, saturation, value);
-            RuntimeValue::Color(color::rgb(r, g, b))
-        },
-    }
+pub fn abs(number: f32) -> f32 {
+    number.abs()
 }
 
-pub fn range(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    match args.len() {
-        1 => {
-            let stop = match &args[0] {
-                RuntimeValue::Number(number) => *number as i32,
-                arg => panic!("Argument: {:?} is not a number", arg)
-            };
+pub fn pow(base: f32, exponent: f32) -> f32 {
+    base.powf(exponent)
+}
 
-            RuntimeValue::Range(0, stop, 1)
-        }
-        2 => {
-            let start = match &args[0] {
-                RuntimeValue::Number(number) => *number as i32,
-                arg => panic!("Argument: {:?} is not a number", arg)
-            };
+pub fn sqrt(number: f32) -> f32 {
+    number.sqrt()
+}
 
-            let stop = match &args[1] {
-                RuntimeValue::Number(number) => *number as i32,
-                arg => panic!("Argument: {:?} is not a number", arg)
-            };
+pub fn sin(number: f32) -> f32 {
+    number.sin()
+}
 
-            RuntimeValue::Range(start, stop, 1)
-        }
-        3 => {
-            let start = match &args[0] {
-                RuntimeValue::Number(number) => *number as i32,
-                arg => panic!("Argument: {:?} is not a number", arg)
-            };
+pub fn cos(number: f32) -> f32 {
+    number.cos()
+}
 
-            let stop = match &args[1] {
-                RuntimeValue::Number(number) => *number as i32,
-                arg => panic!("Argument: {:?} is not a number", arg)
-            };
+fn point(func: &str, value: RuntimeValue) -> [f32; 2] {
+    match &value {
+        RuntimeValue::List(values) => match values.as_slice() {
+            [RuntimeValue::Number(x), RuntimeValue::Number(y)] => [*x, *y],
+            _ => panic!("Invalid argument to '{}': points have to be lists like [x, y], got {}", func, value)
+        },
+        _ => panic!("Invalid argument to '{}': points have to be lists like [x, y], got {}", func, value)
+    }
+}
 
-            let step = match &args[2] {
-                RuntimeValue::Number(number) => *number as usize,
-                arg => panic!("Argument: {:?} is not a number", arg)
-            };
+pub fn polygon(points: Vec<RuntimeValue>) -> ShapeType {
+    let points: Vec<[f32; 2]> = points.into_iter().map(|value| point("polygon", value)).collect();
 
-            RuntimeValue::Range(start, stop, step)
-        }
-        len => panic!("Invalid number of arguments to range takes 1, given {}", len),
+    if points.len() < 3 {
+        panic!("Invalid argument to 'polygon': a polygon needs at least 3 points, got {}", points.len())
     }
+
+    ShapeType::Polygon(points)
 }
 
-pub fn random(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    match args.len() {
-        0 => {
-            RuntimeValue::Number(rand::thread_rng().gen())
-        }
-        1 => {
-            let stop = match args[0] {
-                RuntimeValue::Number(number) => number,
-                _ => panic!("Invalid argument to 'random'")
-            };
+pub fn line(start: RuntimeValue, end: RuntimeValue) -> ShapeType {
+    ShapeType::Polyline(vec![point("line", start), point("line", end)])
+}
 
-            RuntimeValue::Number(rand::thread_rng().gen_range(0.0..stop))
-        }
-        2 => {
-            let start = match args[0] {
-                RuntimeValue::Number(number) => number,
-                _ => panic!("Invalid argument to 'random'")
-            };
+pub fn polyline(points: Vec<RuntimeValue>) -> ShapeType {
+    let points: Vec<[f32; 2]> = points.into_iter().map(|value| point("polyline", value)).collect();
+
+    if points.len() < 2 {
+        panic!("Invalid argument to 'polyline': a polyline needs at least 2 points, got {}", points.len())
+    }
 
-            let stop = match args[1] {
-                RuntimeValue::Number(number) => number,
-                _ => panic!("Invalid argument to 'random'")
-            };
+    ShapeType::Polyline(points)
+}
 
-            RuntimeValue::Number(rand::thread_rng().gen_range(start..stop))
-        }
-        _ => panic!("Invalid number of arguments to 'random'")
+/// A regular polygon with a corner at the top
+pub fn ngon(sides: f32) -> ShapeType {
+    if sides < 3.0 {
+        panic!("Invalid argument to 'ngon': a polygon needs at least 3 sides, got {}", sides)
     }
+
+    let sides = sides as u32;
+    ShapeType::Polygon((0..sides).map(|i| {
+        let radian = i as f32 / sides as f32 * std::f32::consts::TAU;
+        [radian.sin(), radian.cos()]
+    }).collect())
 }
 
-pub fn floor(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    if args.len() != 1 {
-        panic!("Invalid number of arguments to 'floor' function")
+pub fn hollow(shape: ShapeType) -> ShapeType {
+    match shape {
+        ShapeType::Rect | ShapeType::HollowRect => ShapeType::HollowRect,
+        ShapeType::Circle | ShapeType::Ring => ShapeType::Ring,
+        ShapeType::Polygon(points) | ShapeType::HollowPolygon(points) => ShapeType::HollowPolygon(points),
+        shape => panic!("Invalid argument to 'hollow': only rects, circles and polygons can be hollow, got {:?}", shape)
     }
+}
 
-    match &args[0] {
-        RuntimeValue::Number(number) => RuntimeValue::Number(number.floor()),
-        arg => panic!("Invalid argument to 'floor': {}", arg)
+fn check_timer_fn(name: &str, func: &Function) {
+    if !func.parameters.is_empty() {
+        panic!("Invalid argument to '{}': the function '{}' can't have parameters", name, func.name)
     }
 }
 
-pub fn ceil(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    if args.len() != 1 {
-        panic!("Invalid number of arguments to 'ceil' function")
+pub fn after(env: &mut Environment, seconds: f32, func: Function) -> f32 {
+    check_timer_fn("after", &func);
+    env.schedule(seconds, None, func) as f32
+}
+
+pub fn every(env: &mut Environment, seconds: f32, func: Function) -> f32 {
+    if seconds <= 0.0 {
+        panic!("Invalid argument to 'every': the interval has to be more than 0 seconds, got {}", seconds)
     }
 
-    match &args[0] {
-        RuntimeValue::Number(number) => RuntimeValue::Number(number.ceil()),
-        arg => panic!("Invalid argument to 'ceil': {}", arg)
+    check_timer_fn("every", &func);
+    env.schedule(seconds, Some(seconds), func) as f32
+}
+
+pub fn cancel(env: &mut Environment, timer: f32) {
+    env.cancel_timer(timer as u32);
+}
+
+/// Waiting statements are run by the coroutine of 'update', so a call that gets here is somewhere it can't wait
+pub fn wait(_seconds: f32) {
+    panic!("'wait' can only be used as a statement in an 'update' function or in the if, for and while blocks inside it");
+}
+
+pub fn wait_frames(_frames: f32) {
+    panic!("'wait_frames' can only be used as a statement in an 'update' function or in the if, for and while blocks inside it");
+}
+
+pub fn len(value: RuntimeValue) -> f32 {
+    match value {
+        RuntimeValue::List(values) | RuntimeValue::Objects(values) => values.len() as f32,
+        RuntimeValue::Object(map) => map.len() as f32,
+        RuntimeValue::String(string) => string.chars().count() as f32,
+        value => panic!("Invalid argument to 'len': {} doesn't have a length", value)
     }
 }
 
-pub fn abs(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    if args.len() != 1 {
-        panic!("Invalid number of arguments to 'abs' function")
+pub fn keys(map: HashMap<String, RuntimeValue>) -> Vec<RuntimeValue> {
+    let mut keys: Vec<String> = map.into_keys().collect();
+    keys.sort();
+
+    keys.into_iter().map(RuntimeValue::String).collect()
+}
+
+pub fn has(map: HashMap<String, RuntimeValue>, key: String) -> bool {
+    map.contains_key(&key)
+}
+
+pub fn get(map: HashMap<String, RuntimeValue>, key: String, default: Option<RuntimeValue>) -> RuntimeValue {
+    match (map.get(&key), default) {
+        (Some(value), _) => value.clone(),
+        (None, Some(default)) => default,
+        (None, None) => panic!("Object doesn't have property '{}' and no default was given", key)
     }
+}
 
-    match &args[0] {
-        RuntimeValue::Number(number) => RuntimeValue::Number(number.abs()),
-        arg => panic!("Invalid argument to 'abs': {}", arg)
+/// Returns a copy of the object without the property, use as 'self = remove(self, "key")'
+pub fn remove(mut map: HashMap<String, RuntimeValue>, key: String) -> HashMap<String, RuntimeValue> {
+    map.remove(&key);
+    map
+}
+
+pub fn spawn(env: &mut Environment, object: HashMap<String, RuntimeValue>) -> f32 {
+    env.spawn(object) as f32
+}
+
+pub fn destroy(env: &mut Environment, object: HashMap<String, RuntimeValue>) {
+    match object.get("id") {
+        Some(RuntimeValue::Number(id)) => env.destroy(*id as u32),
+        _ => panic!("Invalid argument to 'destroy': the object isn't part of the simulation")
     }
 }
 
-pub fn pow(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    if args.len() != 2 {
-        panic!("Invalid number of arguments to 'pow' function")
+/// The id of an object of the simulation, or the id itself
+fn object_id(func: &str, value: RuntimeValue) -> u32 {
+    match value {
+        RuntimeValue::Number(id) => id as u32,
+        RuntimeValue::Object(map) => match map.get("id") {
+            Some(RuntimeValue::Number(id)) => *id as u32,
+            _ => panic!("Invalid argument to '{}': the object isn't part of the simulation", func)
+        },
+        value => panic!("Invalid argument to '{}': expected an object or an id, got {}", func, value)
     }
+}
 
-    let base = match &args[0] {
-        RuntimeValue::Number(number) => number,
-        arg => panic!("Invalid argument to 'abs': {}", arg)
-    };
+pub fn joint(env: &mut Environment, first: RuntimeValue, second: RuntimeValue, options: Option<HashMap<String, RuntimeValue>>) -> f32 {
+    let options = options.unwrap_or_default();
+    env.add_joint(object_id("joint", first), object_id("joint", second), options) as f32
+}
 
-    let exponent = match &args[1] {
-        RuntimeValue::Number(number) => number,
-        arg => panic!("Invalid argument to 'abs': {}", arg)
-    };
+pub fn spring(env: &mut Environment, first: RuntimeValue, second: RuntimeValue, stiffness: f32, damping: f32, break_force: Option<f32>) -> f32 {
+    let mut options = HashMap::from([
+        ("kind".to_string(), RuntimeValue::String("spring".to_string())),
+        ("stiffness".to_string(), RuntimeValue::Number(stiffness)),
+        ("damping".to_string(), RuntimeValue::Number(damping))
+    ]);
+    if let Some(break_force) = break_force {
+        options.insert("break_force".to_string(), RuntimeValue::Number(break_force));
+    }
 
-    RuntimeValue::Number(base.powf(*exponent))
+    env.add_joint(object_id("spring", first), object_id("spring", second), options) as f32
 }
 
-pub fn sqrt(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    if args.len() != 1 {
-        panic!("Invalid number of arguments to 'abs' function")
+pub fn rope(env: &mut Environment, first: RuntimeValue, second: RuntimeValue, length: f32, break_force: Option<f32>) -> f32 {
+    let mut options = HashMap::from([
+        ("kind".to_string(), RuntimeValue::String("rope".to_string())),
+        ("length".to_string(), RuntimeValue::Number(length))
+    ]);
+    if let Some(break_force) = break_force {
+        options.insert("break_force".to_string(), RuntimeValue::Number(break_force));
     }
 
-    match &args[0] {
-        RuntimeValue::Number(number) => RuntimeValue::Number(number.sqrt()),
-        arg => panic!("Invalid argument to 'abs': {}", arg)
+    env.add_joint(object_id("rope", first), object_id("rope", second), options) as f32
+}
+
+pub fn break_joint(env: &mut Environment, id: f32) {
+    env.remove_joint(id as u32);
+}
+
+pub fn apply_force(env: &mut Environment, object: RuntimeValue, fx: f32, fy: f32) {
+    env.push(ForceRequest::Force { id: object_id("apply_force", object), force: [fx, fy] });
+}
+
+pub fn apply_impulse(env: &mut Environment, object: RuntimeValue, ix: f32, iy: f32) {
+    env.push(ForceRequest::Impulse { id: object_id("apply_impulse", object), impulse: [ix, iy] });
+}
+
+pub fn apply_impulse_at_point(env: &mut Environment, object: RuntimeValue, ix: f32, iy: f32, x: f32, y: f32) {
+    env.push(ForceRequest::ImpulseAtPoint { id: object_id("apply_impulse_at_point", object), impulse: [ix, iy], point: [x, y] });
+}
+
+pub fn apply_torque(env: &mut Environment, object: RuntimeValue, torque: f32) {
+    env.push(ForceRequest::Torque { id: object_id("apply_torque", object), torque });
+}
+
+pub fn explode(env: &mut Environment, x: f32, y: f32, strength: f32, radius: f32) {
+    if radius <= 0.0 {
+        panic!("Invalid argument to 'explode': the radius has to be more than 0, got {}", radius)
     }
+
+    env.push(ForceRequest::Explosion { center: [x, y], strength, radius });
 }
 
-pub fn sin(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    if args.len() != 1 {
-        panic!("Invalid number of arguments to 'abs' function")
+pub fn world_count(env: &mut Environment) -> f32 {
+    env.world_len() as f32
+}
+
+pub fn world_find(env: &mut Environment, tag: String) -> Vec<RuntimeValue> {
+    env.world_find(&tag).into_iter().map(RuntimeValue::Object).collect()
+}
+
+/// Ids are whole numbers, so other numbers don't belong to any object
+fn world_object(env: &Environment, id: f32) -> Option<HashMap<String, RuntimeValue>> {
+    if id < 0.0 || id.fract() != 0.0 {
+        return None
     }
 
-    match &args[0] {
-        RuntimeValue::Number(number) => RuntimeValue::Number(number.sin()),
-        arg => panic!("Invalid argument to 'abs': {}", arg)
+    env.world_object(id as u32)
+}
+
+pub fn world_get(env: &mut Environment, id: f32) -> HashMap<String, RuntimeValue> {
+    match world_object(env, id) {
+        Some(object) => object,
+        None => panic!("There is no object with id {} in the world", id)
     }
 }
 
-pub fn cos(args: Vec<RuntimeValue>, _env: &mut Environment) -> RuntimeValue {
-    if args.len() != 1 {
-        panic!("Invalid number of arguments to 'abs' function")
+pub fn world_has(env: &mut Environment, id: f32) -> bool {
+    world_object(env, id).is_some()
+}
+
+pub fn type_of(value: RuntimeValue) -> String {
+    value.type_name().to_string()
+}
+
+pub fn assert(condition: bool, message: Option<String>) -> bool {
+    match (condition, message) {
+        (true, _) => true,
+        (false, Some(message)) => panic!("Assertion failed: {}", message),
+        (false, None) => panic!("Assertion failed")
     }
+}
 
-    match &args[0] {
-        RuntimeValue::Number(number) => RuntimeValue::Number(number.cos()),
-        arg => panic!("Invalid argument to 'abs': {}", arg)
+pub fn assert_eq(left: RuntimeValue, right: RuntimeValue) -> bool {
+    if left != right {
+        panic!("Assertion failed: left == right\n  left: {}\n right: {}", left, right)
     }
+
+    true
 }
@@ -0,0 +1,63 @@
+use super::Function;
+
+#[derive(Debug, Clone)]
+struct Timer {
+    id: u32,
+    /// The simulation time at which the function is called next
+    due: f32,
+    /// The time between calls for timers created with 'every'
+    interval: Option<f32>,
+    func: Function
+}
+
+/// Functions scheduled to be called at a simulation time
+#[derive(Debug, Default)]
+pub struct Timers {
+    next_id: u32,
+    time: f32,
+    timers: Vec<Timer>
+}
+
+impl Timers {
+    /// Call the function after the delay, and then every interval if there is one, returns the id of the timer
+    pub fn schedule(&mut self, delay: f32, interval: Option<f32>, func: Function) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.timers.push(Timer {
+            id,
+            due: self.time + delay,
+            interval,
+            func
+        });
+
+        id
+    }
+
+    pub fn cancel(&mut self, id: u32) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    /// Move to the time of a new frame and take the functions that are due, in the order they are due.
+    /// Timers are due on the frame closest to their time, so half a frame early at most
+    pub fn advance(&mut self, time: f32, dt: f32) -> Vec<Function> {
+        self.time = time;
+
+        let mut due = vec![];
+        for timer in &mut self.timers {
+            while timer.due <= time + dt / 2.0 {
+                due.push((timer.due, timer.id, timer.func.clone()));
+
+                match timer.interval {
+                    Some(interval) => timer.due += interval,
+                    None => break
+                }
+            }
+        }
+
+        self.timers.retain(|timer| timer.interval.is_some() || timer.due > time + dt / 2.0);
+
+        due.sort_by(|(left_due, left_id, _), (right_due, right_id, _)| left_due.total_cmp(right_due).then(left_id.cmp(right_id)));
+        due.into_iter().map(|(_, _, func)| func).collect()
+    }
+}
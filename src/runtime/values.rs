@@ -1,10 +1,8 @@
 use std::{collections::HashMap, fmt::{Debug, Display}};
 
-use nannou::color::Rgb;
-
 use crate::frontend::{ast::Statement, ShapeType};
 
-use super::Environment;
+use super::{Color, Environment, NativeFn};
 
 #[derive(Debug, Clone)]
 pub struct Function {
@@ -29,35 +27,93 @@ impl Function {
 pub enum RuntimeValue {
     Number(f32),
     Boolean(bool),
+    String(String),
 
-    NativeFn(fn(args: Vec<RuntimeValue>, env: &mut Environment) -> RuntimeValue),
+    NativeFn(NativeFn),
     Function(Function),
     Range(i32, i32, usize),
 
     Object(HashMap<String, RuntimeValue>),
     Objects(Vec<RuntimeValue>),
+    List(Vec<RuntimeValue>),
+    /// The objects of the running simulation
+    World,
 
     Shape(ShapeType),
-    Color(Rgb<u8>),
+    Color(Color),
     Note(String)
 }
 
+impl RuntimeValue {
+    /// The name of the type of this value
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Number(_) => "number",
+            Self::Boolean(_) => "boolean",
+            Self::String(_) => "string",
+
+            Self::NativeFn(_) => "native_fn",
+            Self::Function(_) => "function",
+            Self::Range(_, _, _) => "range",
+
+            Self::Object(_) => "object",
+            Self::Objects(_) => "objects",
+            Self::List(_) => "list",
+            Self::World => "world",
+
+            Self::Shape(_) => "shape",
+            Self::Color(_) => "color",
+            Self::Note(_) => "note"
+        }
+    }
+}
+
 impl Display for RuntimeValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Number(number) => write!(f, "{}", number),
             Self::Boolean(boolean) => write!(f, "{}", boolean),
+            Self::String(string) => write!(f, "{}", string),
 
-            Self::NativeFn(func) => write!(f, "native fn ({:?})", func),
+            Self::NativeFn(func) => write!(f, "native fn {:?}", func),
             Self::Function(Function { name, parameters, body: _, declaration_env: _ }) => write!(f, "{}({:?})", name, parameters),
             Self::Range(start, stop, step) => write!(f, "range({}, {}, {})", start, stop, step),
 
             Self::Object(map) => write!(f, "Object {{ {:?} }}", map),
             Self::Objects(objects) => write!(f, "Objects {{ {:?} }}", objects),
+            Self::List(values) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Self::World => write!(f, "world"),
 
             Self::Shape(shape) => write!(f, "{:?}", shape),
-            Self::Color(color) => write!(f, "{:?}", color),
+            Self::Color(color) => write!(f, "{}", color),
             Self::Note(note) => write!(f, "{}", note)
         }
     }
 }
+
+impl PartialEq for RuntimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(left), Self::Number(right)) => left == right,
+            (Self::Boolean(left), Self::Boolean(right)) => left == right,
+            (Self::String(left), Self::String(right)) => left == right,
+
+            (Self::Range(left_start, left_stop, left_step), Self::Range(right_start, right_stop, right_step)) => {
+                left_start == right_start && left_stop == right_stop && left_step == right_step
+            }
+
+            (Self::Object(left), Self::Object(right)) => left == right,
+            (Self::Objects(left), Self::Objects(right)) => left == right,
+            (Self::List(left), Self::List(right)) => left == right,
+            (Self::World, Self::World) => true,
+
+            (Self::Shape(left), Self::Shape(right)) => left == right,
+            (Self::Color(left), Self::Color(right)) => left == right,
+            (Self::Note(left), Self::Note(right)) => left == right,
+            _ => false
+        }
+    }
+}
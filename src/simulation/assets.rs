@@ -0,0 +1,23 @@
+use std::{env, path::{Path, PathBuf}};
+
+/// Find the assets directory in one of the parents of the executable or the current directory
+pub fn find_assets_path() -> Option<PathBuf> {
+    let exe_path = env::current_exe().ok()?;
+    let exe_dirs = exe_path.ancestors().skip(1).take(5).map(Path::to_path_buf);
+
+    exe_dirs.chain(env::current_dir().ok())
+        .map(|dir| dir.join("assets"))
+        .find(|path| path.is_dir())
+}
+
+/// Get the names of all the notes in the assets directory
+pub fn note_names(assets_path: &Path) -> Vec<String> {
+    let notes_path = assets_path.join("notes");
+
+    notes_path.read_dir().expect("Failed to read dir").map(
+        |file| file.expect("Invalid file").file_name()
+            .into_string().expect("Failed to convert to string")
+            .strip_suffix(".wav").expect("Failed to strip .wav suffix")
+            .to_string()
+    ).collect()
+}
@@ -4,6 +4,12 @@ pub struct Audio {
     sounds: Vec<(audrey::read::BufFileReader, f32)>,
 }
 
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Audio {
     pub fn new() -> Self {
         Self {
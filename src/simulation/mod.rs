@@ -1,8 +1,13 @@
+mod assets;
+#[cfg(feature = "audio")]
 mod audio;
-mod model;
 mod object;
 mod physics;
+mod scene;
 
-pub use audio::Audio;
-pub use model::{model, update, view};
+pub use assets::{find_assets_path, note_names};
+#[cfg(feature = "audio")]
+pub use audio::{play_audio, Audio};
 pub use object::{Object, ObjectBuilder};
+pub use physics::{ColliderSettings, ContactDetails, ContactEvent, JointKind, Physics};
+pub use scene::{CollisionEvent, ObjectState, Scene, SceneBuilder};
@@ -1,30 +1,63 @@
 use core::panic;
-use std::{collections::HashMap, path::Path};
+use std::collections::HashMap;
 
 use nalgebra::SimdComplexField;
-use nannou::prelude::*;
+#[cfg(feature = "window")]
+use nannou::prelude::{pt2, Draw, Point2};
 use rapier2d::prelude::*;
 use rand::Rng;
 
-use crate::{frontend::{ast::Statement, ShapeType}, runtime::{eval_runtime_object_expr, Environment, Function, RuntimeValue}};
+use crate::{frontend::{ast::Statement, ShapeType}, runtime::{eval_runtime_object_expr, uses_wait, Color, Coroutine, CoroutineStep, Environment, Function, RuntimeValue}};
 
-use super::{physics::Physics, Audio};
+#[cfg(feature = "window")]
+use super::physics::{arc_angles, hollow_outline};
+use super::physics::{ColliderSettings, Physics};
+
+/// Properties with a special meaning, these can't be used as custom properties
+const PROPERTIES: [&str; 45] = [
+    "id", "tags", "shape", "x", "y", "rotation", "width", "height", "size", "radius", "start_angle", "end_angle", "gap", "gravity", "bounciness", "fixed", "kinematic", "sensor",
+    "speed", "vx", "vy", "direction", "angular_velocity",
+    "friction", "density", "mass", "linear_damping", "angular_damping", "bounciness_combine", "friction_combine", "lock_rotation",
+    "color", "stroke_color", "stroke_weight", "hit_note", "hit_note_volume", "trail", "lifetime", "lifetime_frames", "fade_out", "age",
+    "update", "hit", "hit_end", "touching"
+];
 
 pub struct ObjectBuilder {
     pub shape: ShapeType,
 
     pub pos: Vector<Real>,
+    /// In degrees counterclockwise
+    pub rotation: f32,
     pub vel: Vector<Real>,
+    pub speed: Option<f32>,
+    /// In degrees counterclockwise, random when only the speed is given
+    pub direction: Option<f32>,
+    /// In degrees per second counterclockwise
+    pub angular_velocity: f32,
 
     pub width: f32,
     pub height: f32,
+    pub radius: f32,
+    pub start_angle: f32,
+    pub end_angle: f32,
 
     pub gravity: f32,
     pub bounciness: f32,
     pub fixed: bool,
-
-    pub color: Rgb<u8>,
-    pub stroke_color: Rgb<u8>,
+    /// Moves with its velocity without being pushed by other objects
+    pub kinematic: bool,
+
+    pub friction: f32,
+    pub density: f32,
+    pub mass: Option<f32>,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub bounciness_combine: CoefficientCombineRule,
+    pub friction_combine: CoefficientCombineRule,
+    pub lock_rotation: bool,
+
+    pub color: Color,
+    pub stroke_color: Color,
     pub stroke_weight: f32,
 
     pub hit_note: String,
@@ -32,38 +65,81 @@ pub struct ObjectBuilder {
 
     pub frames_per_trail_obj: Option<u128>,
 
+    pub lifetime: Option<f32>,
+    pub lifetime_frames: Option<u128>,
+    pub fade_out: f32,
+
+    pub sensor: bool,
+
+    pub tags: Vec<String>,
+
     pub update_fn: Option<Function>,
     pub hit_fn: Option<Function>,
+    pub hit_end_fn: Option<Function>,
+    pub touching_fn: Option<Function>,
 
     pub others: HashMap<String, RuntimeValue>
 }
 
+impl Default for ObjectBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ObjectBuilder {
     pub fn new() -> Self {
         Self {
             shape: ShapeType::Circle,
 
             pos: vector![0.0, 0.0],
+            rotation: 0.0,
             vel: vector![0.0, 0.0],
+            speed: None,
+            direction: None,
+            angular_velocity: 0.0,
 
             width: 10.0,
             height: 10.0,
+            radius: 5.0,
+            start_angle: 0.0,
+            end_angle: 360.0,
 
             gravity: 0.0,
             bounciness: 0.5,
             fixed: false,
-
-            color: WHITE,
-            stroke_color: WHITE,
+            kinematic: false,
+
+            friction: 0.5,
+            density: 1.0,
+            mass: None,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            bounciness_combine: CoefficientCombineRule::Average,
+            friction_combine: CoefficientCombineRule::Average,
+            lock_rotation: false,
+
+            color: Color::new(255, 255, 255),
+            stroke_color: Color::new(255, 255, 255),
             stroke_weight: 3.0,
 
             hit_note: "A0vH".to_string(),
             hit_note_volume: 0.0,
 
             frames_per_trail_obj: None,
-            
+
+            lifetime: None,
+            lifetime_frames: None,
+            fade_out: 0.0,
+
+            sensor: false,
+
+            tags: vec![],
+
             update_fn: None,
             hit_fn: None,
+            hit_end_fn: None,
+            touching_fn: None,
 
             others: HashMap::new()
         }
@@ -71,21 +147,49 @@ impl ObjectBuilder {
 
     pub fn from_map(map: HashMap<String, RuntimeValue>, physics: &mut Physics) -> Object {
         let mut builder = ObjectBuilder::new();
+
+        // The maps of objects have all of these keys, so the ones that set the same thing are applied after the others
+        let mut size = None;
+        let (mut width, mut height) = (None, None);
+        let mut gap = None;
+        let (mut start_angle, mut end_angle) = (None, None);
+        let (mut density, mut mass) = (None, None);
+
         for (key, value) in map {
             builder = match (key.as_str(), value) {
                 ("shape", RuntimeValue::Shape(shape)) => builder.shape(shape),
 
                 ("x", RuntimeValue::Number(number)) => builder.x(number),
                 ("y", RuntimeValue::Number(number)) => builder.y(number),
+                ("rotation", RuntimeValue::Number(number)) => builder.rotation(number),
                 ("speed", RuntimeValue::Number(number)) => builder.speed(number),
-
-                ("width", RuntimeValue::Number(number)) => builder.width(number),
-                ("height", RuntimeValue::Number(number)) => builder.height(number),
-                ("size", RuntimeValue::Number(number)) => builder.size(number),
+                ("vx", RuntimeValue::Number(number)) => builder.vx(number),
+                ("vy", RuntimeValue::Number(number)) => builder.vy(number),
+                ("direction", RuntimeValue::Number(number)) => builder.direction(number),
+                ("angular_velocity", RuntimeValue::Number(number)) => builder.angular_velocity(number),
+
+                ("width", RuntimeValue::Number(number)) => { width = Some(number); builder },
+                ("height", RuntimeValue::Number(number)) => { height = Some(number); builder },
+                ("size", RuntimeValue::Number(number)) => { size = Some(number); builder },
+                ("radius", RuntimeValue::Number(number)) => builder.radius(number),
+                ("start_angle", RuntimeValue::Number(number)) => { start_angle = Some(number); builder },
+                ("end_angle", RuntimeValue::Number(number)) => { end_angle = Some(number); builder },
+                ("gap", RuntimeValue::Number(number)) => { gap = Some(number); builder },
 
                 ("gravity", RuntimeValue::Number(number)) => builder.gravity(number),
                 ("bounciness", RuntimeValue::Number(number)) => builder.bounciness(number),
                 ("fixed", RuntimeValue::Boolean(boolean)) => builder.fixed(boolean),
+                ("kinematic", RuntimeValue::Boolean(boolean)) => builder.kinematic(boolean),
+                ("sensor", RuntimeValue::Boolean(boolean)) => builder.sensor(boolean),
+
+                ("friction", RuntimeValue::Number(number)) => builder.friction(number),
+                ("density", RuntimeValue::Number(number)) => { density = Some(number); builder },
+                ("mass", RuntimeValue::Number(number)) => { mass = Some(number); builder },
+                ("linear_damping", RuntimeValue::Number(number)) => builder.linear_damping(number),
+                ("angular_damping", RuntimeValue::Number(number)) => builder.angular_damping(number),
+                ("bounciness_combine", RuntimeValue::String(rule)) => builder.bounciness_combine(combine_rule(&rule)),
+                ("friction_combine", RuntimeValue::String(rule)) => builder.friction_combine(combine_rule(&rule)),
+                ("lock_rotation", RuntimeValue::Boolean(boolean)) => builder.lock_rotation(boolean),
 
                 ("color", RuntimeValue::Color(color)) => builder.color(color),
                 ("stroke_color", RuntimeValue::Color(color)) => builder.stroke_color(color),
@@ -95,14 +199,54 @@ impl ObjectBuilder {
                 ("hit_note_volume", RuntimeValue::Number(number)) => builder.hit_note_volume(number),
 
                 ("trail", RuntimeValue::Number(number)) => builder.frames_per_trail_obj(number),
+                ("tags", RuntimeValue::List(tags)) => builder.tags(tag_names(tags)),
+                ("lifetime", RuntimeValue::Number(number)) => builder.lifetime(number),
+                ("lifetime_frames", RuntimeValue::Number(number)) => builder.lifetime_frames(number),
+                ("fade_out", RuntimeValue::Number(number)) => builder.fade_out(number),
+
+                // Every object gets its own id and age, so these are ignored when copying another object
+                ("id" | "age", RuntimeValue::Number(_)) => builder,
 
                 ("update", RuntimeValue::Function(Function { name, parameters, body, declaration_env })) => builder.update(name, parameters, body, declaration_env),
                 ("hit", RuntimeValue::Function(Function { name, parameters, body, declaration_env })) => builder.hit(name, parameters, body, declaration_env),
+                ("hit_end", RuntimeValue::Function(Function { name, parameters, body, declaration_env })) => builder.hit_end(name, parameters, body, declaration_env),
+                ("touching", RuntimeValue::Function(Function { name, parameters, body, declaration_env })) => builder.touching(name, parameters, body, declaration_env),
 
+                (key, value) if PROPERTIES.contains(&key) => panic!("Invalid value for property '{}': {}", key, value),
                 (key, value) => builder.other(key.to_string(), value)
             }
         }
 
+        // 'width' and 'height' win over 'size', so copies of rects keep their sides
+        if let Some(size) = size {
+            builder = builder.size(size);
+        }
+        if let Some(width) = width {
+            builder = builder.width(width);
+        }
+        if let Some(height) = height {
+            builder = builder.height(height);
+        }
+
+        // The same for 'start_angle' and 'end_angle' over 'gap', so copies of arcs keep their angles
+        if let Some(gap) = gap {
+            builder = builder.gap(gap);
+        }
+        if let Some(start_angle) = start_angle {
+            builder = builder.start_angle(start_angle);
+        }
+        if let Some(end_angle) = end_angle {
+            builder = builder.end_angle(end_angle);
+        }
+
+        // A fixed 'mass' wins over the 'density', which is always in the map
+        if let Some(density) = density {
+            builder = builder.density(density);
+        }
+        if let Some(mass) = mass {
+            builder = builder.mass(mass);
+        }
+
         builder.build(physics)
     }
 
@@ -121,14 +265,48 @@ impl ObjectBuilder {
         self
     }
 
+    /// In degrees counterclockwise
+    pub fn rotation(mut self, rotation: f32) -> ObjectBuilder {
+        self.rotation = rotation;
+        self
+    }
+
     pub fn speed(mut self, speed: f32) -> ObjectBuilder {
-        let vel = vector!(
-            rand::thread_rng().gen_range(-1.0..=1.0),
-                rand::thread_rng().gen_range(-1.0..=1.0)).normalize() * speed;
-        self.vel = vel;
+        self.speed = Some(speed);
+        self
+    }
+
+    pub fn vx(mut self, vx: f32) -> ObjectBuilder {
+        self.vel.x = vx;
+        self
+    }
+
+    pub fn vy(mut self, vy: f32) -> ObjectBuilder {
+        self.vel.y = vy;
         self
     }
 
+    /// In degrees counterclockwise
+    pub fn direction(mut self, direction: f32) -> ObjectBuilder {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// In degrees per second counterclockwise
+    pub fn angular_velocity(mut self, angular_velocity: f32) -> ObjectBuilder {
+        self.angular_velocity = angular_velocity;
+        self
+    }
+
+    /// The velocity from 'vx' and 'vy', or from the speed and direction when one of them is given
+    pub fn velocity(&self) -> Vector<Real> {
+        match (self.speed, self.direction) {
+            (None, None) => self.vel,
+            (Some(speed), None) => polar(speed, rand::thread_rng().gen_range(0.0..360.0)),
+            (speed, Some(direction)) => polar(speed.unwrap_or(self.vel.norm()), direction)
+        }
+    }
+
     pub fn width(mut self, width: f32) -> ObjectBuilder {
         self.width = width;
         self
@@ -143,6 +321,29 @@ impl ObjectBuilder {
         self.width(size).height(size)
     }
 
+    /// The radius of the corners of a rounded rect
+    pub fn radius(mut self, radius: f32) -> ObjectBuilder {
+        self.radius = radius;
+        self
+    }
+
+    /// Where an arc starts in degrees counterclockwise from the right
+    pub fn start_angle(mut self, start_angle: f32) -> ObjectBuilder {
+        self.start_angle = start_angle;
+        self
+    }
+
+    /// Where an arc ends in degrees counterclockwise from the right
+    pub fn end_angle(mut self, end_angle: f32) -> ObjectBuilder {
+        self.end_angle = end_angle;
+        self
+    }
+
+    /// Leave an opening of the degrees on the right side of an arc
+    pub fn gap(self, gap: f32) -> ObjectBuilder {
+        self.start_angle(gap / 2.0).end_angle(360.0 - gap / 2.0)
+    }
+
     pub fn gravity(mut self, gravity: f32) -> ObjectBuilder {
         self.gravity = gravity;
         self
@@ -158,12 +359,71 @@ impl ObjectBuilder {
         self
     }
 
-    pub fn color(mut self, color: Rgb<u8>) -> ObjectBuilder {
+    /// Move with the velocity and angular velocity, pushing other objects without being pushed back
+    pub fn kinematic(mut self, kinematic: bool) -> ObjectBuilder {
+        self.kinematic = kinematic;
+        self
+    }
+
+    pub fn sensor(mut self, sensor: bool) -> ObjectBuilder {
+        self.sensor = sensor;
+        self
+    }
+
+    pub fn friction(mut self, friction: f32) -> ObjectBuilder {
+        self.friction = friction;
+        self
+    }
+
+    /// The mass per area, the mass follows from the size
+    pub fn density(mut self, density: f32) -> ObjectBuilder {
+        self.density = density;
+        self.mass = None;
+        self
+    }
+
+    /// The mass regardless of the size
+    pub fn mass(mut self, mass: f32) -> ObjectBuilder {
+        self.mass = Some(mass);
+        self
+    }
+
+    /// How quickly the object slows down by itself
+    pub fn linear_damping(mut self, linear_damping: f32) -> ObjectBuilder {
+        self.linear_damping = linear_damping;
+        self
+    }
+
+    /// How quickly the object stops turning by itself
+    pub fn angular_damping(mut self, angular_damping: f32) -> ObjectBuilder {
+        self.angular_damping = angular_damping;
+        self
+    }
+
+    /// How the bounciness of two objects is combined when they hit
+    pub fn bounciness_combine(mut self, rule: CoefficientCombineRule) -> ObjectBuilder {
+        self.bounciness_combine = rule;
+        self
+    }
+
+    /// How the friction of two objects is combined when they touch
+    pub fn friction_combine(mut self, rule: CoefficientCombineRule) -> ObjectBuilder {
+        self.friction_combine = rule;
+        self
+    }
+
+    /// Keep the object from turning
+    pub fn lock_rotation(mut self, lock_rotation: bool) -> ObjectBuilder {
+        self.lock_rotation = lock_rotation;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> ObjectBuilder {
         self.color = color;
         self
     }
 
-    pub fn stroke_color(mut self, stroke_color: Rgb<u8>) -> ObjectBuilder {
+    pub fn stroke_color(mut self, stroke_color: Color) -> ObjectBuilder {
         self.stroke_color = stroke_color;
         self
     }
@@ -188,6 +448,30 @@ impl ObjectBuilder {
         self
     }
 
+    /// Names to find the object with using 'world.find'
+    pub fn tags(mut self, tags: Vec<String>) -> ObjectBuilder {
+        self.tags = tags;
+        self
+    }
+
+    /// Remove the object after this many seconds
+    pub fn lifetime(mut self, lifetime: f32) -> ObjectBuilder {
+        self.lifetime = Some(lifetime);
+        self
+    }
+
+    /// Remove the object after this many frames
+    pub fn lifetime_frames(mut self, lifetime_frames: f32) -> ObjectBuilder {
+        self.lifetime_frames = Some(lifetime_frames.max(0.0) as u128);
+        self
+    }
+
+    /// Fade the object out during the last seconds of its lifetime
+    pub fn fade_out(mut self, fade_out: f32) -> ObjectBuilder {
+        self.fade_out = fade_out;
+        self
+    }
+
     pub fn update(mut self, name: String, parameters: Vec<String>, body: Vec<Statement>, declaration_env: Environment) -> ObjectBuilder {
         self.update_fn = Some(Function::new(name, parameters, body, declaration_env));
         self
@@ -198,16 +482,45 @@ impl ObjectBuilder {
         self
     }
 
+    pub fn hit_end(mut self, name: String, parameters: Vec<String>, body: Vec<Statement>, declaration_env: Environment) -> ObjectBuilder {
+        self.hit_end_fn = Some(Function::new(name, parameters, body, declaration_env));
+        self
+    }
+
+    pub fn touching(mut self, name: String, parameters: Vec<String>, body: Vec<Statement>, declaration_env: Environment) -> ObjectBuilder {
+        self.touching_fn = Some(Function::new(name, parameters, body, declaration_env));
+        self
+    }
+
     pub fn other(mut self, key: String, value: RuntimeValue) -> ObjectBuilder {
         self.others.insert(key, value);
         self
     }
 
+    pub fn collider_settings(&self) -> ColliderSettings {
+        ColliderSettings {
+            shape: self.shape.clone(),
+            width: self.width,
+            height: self.height,
+            radius: self.radius,
+            start_angle: self.start_angle,
+            end_angle: self.end_angle,
+            stroke_weight: self.stroke_weight,
+            bounciness: self.bounciness,
+            friction: self.friction,
+            density: self.density,
+            mass: self.mass,
+            bounciness_combine: self.bounciness_combine,
+            friction_combine: self.friction_combine,
+            sensor: self.sensor
+        }
+    }
+
     pub fn build(self, physics: &mut Physics) -> Object {
         let handle = physics.add(&self);
 
-        if self.shape != ShapeType::Rect && self.width != self.height {
-            panic!("A circle and ring must have the same width and height")
+        if matches!(self.shape, ShapeType::Circle | ShapeType::Ring | ShapeType::Arc) && self.width != self.height {
+            panic!("A circle, ring and arc must have the same width and height")
         }
 
         Object {
@@ -216,6 +529,9 @@ impl ObjectBuilder {
 
                 width: self.width,
                 height: self.height,
+                radius: self.radius,
+                start_angle: self.start_angle,
+                end_angle: self.end_angle,
 
                 color: self.color,
 
@@ -229,10 +545,29 @@ impl ObjectBuilder {
             frames_per_trail_obj: self.frames_per_trail_obj,
             trail_objs: vec![],
 
+            id: 0,
+            tags: self.tags,
+            lifetime: self.lifetime,
+            lifetime_frames: self.lifetime_frames,
+            fade_out: self.fade_out,
+            age: 0.0,
+            frames: 0,
+
             bounciness: self.bounciness,
+            friction: self.friction,
+            density: self.density,
+            mass: self.mass,
+            bounciness_combine: self.bounciness_combine,
+            friction_combine: self.friction_combine,
+            sensor: self.sensor,
+
+            update_waits: self.update_fn.as_ref().is_some_and(uses_wait),
+            update_coroutine: None,
 
             update_fn: self.update_fn,
             hit_fn: self.hit_fn,
+            hit_end_fn: self.hit_end_fn,
+            touching_fn: self.touching_fn,
 
             handle,
 
@@ -241,27 +576,69 @@ impl ObjectBuilder {
     }
 }
 
+/// A vector with the length and the angle in degrees counterclockwise from the right
+fn polar(length: f32, degrees: f32) -> Vector<Real> {
+    let radian = degrees.to_radians();
+    vector![radian.cos(), radian.sin()] * length
+}
+
+/// The angle of a vector in degrees counterclockwise from the right
+fn direction(vector: Vector<Real>) -> f32 {
+    vector.y.atan2(vector.x).to_degrees()
+}
+
+/// The rule for combining the bounciness or friction of two objects from its name in a script
+fn combine_rule(name: &str) -> CoefficientCombineRule {
+    match name {
+        "average" => CoefficientCombineRule::Average,
+        "min" => CoefficientCombineRule::Min,
+        "multiply" => CoefficientCombineRule::Multiply,
+        "max" => CoefficientCombineRule::Max,
+        name => panic!("Invalid combine rule '{}', expected average, min, multiply or max", name)
+    }
+}
+
+fn combine_rule_name(rule: CoefficientCombineRule) -> &'static str {
+    match rule {
+        CoefficientCombineRule::Average => "average",
+        CoefficientCombineRule::Min => "min",
+        CoefficientCombineRule::Multiply => "multiply",
+        CoefficientCombineRule::Max => "max"
+    }
+}
+
+fn tag_names(tags: Vec<RuntimeValue>) -> Vec<String> {
+    tags.into_iter().map(|tag| match tag {
+        RuntimeValue::String(tag) => tag,
+        value => panic!("Invalid tag, tags have to be strings: {}", value)
+    }).collect()
+}
+
 #[derive(Debug, Clone)]
 struct ObjectDrawing {
     shape: ShapeType,
 
     width: f32,
     height: f32,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
 
-    color: Rgb<u8>,
-    stroke_color: Rgb<u8>,
+    color: Color,
+    stroke_color: Color,
     stroke_weight: f32,
 }
 
+#[cfg(feature = "window")]
 impl ObjectDrawing {
-    fn draw(&self, draw: &Draw, pos: Translation<f32>, rot: f32) {
-        match self.shape {
+    fn draw(&self, draw: &Draw, pos: Translation<f32>, rot: f32, alpha: f32) {
+        match &self.shape {
             ShapeType::Circle => {
                 draw.ellipse()
                     .x_y(pos.x, pos.y)
                     .radius(self.width)
-                    .color(self.color)
-                    .stroke_color(self.stroke_color)
+                    .color(self.color.with_alpha(alpha))
+                    .stroke_color(self.stroke_color.with_alpha(alpha))
                     .stroke_weight(self.stroke_weight);
             }
             ShapeType::Rect => {
@@ -269,36 +646,105 @@ impl ObjectDrawing {
                     .x_y(pos.x, pos.y)
                     .w_h(2.0 * self.width, 2.0 * self.height)
                     .rotate(rot)
-                    .color(self.color)
-                    .stroke_color(self.stroke_color)
+                    .color(self.color.with_alpha(alpha))
+                    .stroke_color(self.stroke_color.with_alpha(alpha))
                     .stroke_weight(self.stroke_weight);
             }
             ShapeType::Ring => {
                 let points = (0..=360).map(|i| {
-                    let radian = deg_to_rad(i as f32);
+                    let radian = (i as f32).to_radians();
 
                     let x = pos.x + radian.sin() * self.width;
                     let y = pos.y + radian.cos() * self.width;
 
-                    (pt2(x, y), self.color)
+                    (pt2(x, y), self.color.with_alpha(alpha))
+                });
+
+                draw.polyline()
+                    .stroke_weight(self.stroke_weight)
+                    .points_colored(points);
+            }
+            ShapeType::Arc => {
+                let points = arc_angles(self.start_angle, self.end_angle, 2.0).map(|radian| {
+                    let x = pos.x + (radian + rot).cos() * self.width;
+                    let y = pos.y + (radian + rot).sin() * self.width;
+
+                    (pt2(x, y), self.color.with_alpha(alpha))
                 });
 
                 draw.polyline()
                     .stroke_weight(self.stroke_weight)
                     .points_colored(points);
             }
+            ShapeType::HollowRect | ShapeType::HollowPolygon(_) => {
+                draw.polyline()
+                    .stroke_weight(self.stroke_weight)
+                    .join_round()
+                    .color(self.color.with_alpha(alpha))
+                    .points(hollow_outline(&self.shape).into_iter().map(|[x, y]| pt2(x * self.width, y * self.height)))
+                    .x_y(pos.x, pos.y)
+                    .rotate(rot);
+            }
+            ShapeType::Polyline(points) => {
+                draw.polyline()
+                    .stroke_weight(self.stroke_weight)
+                    .color(self.color.with_alpha(alpha))
+                    .points(points.iter().map(|[x, y]| pt2(*x, *y)))
+                    .x_y(pos.x, pos.y)
+                    .rotate(rot);
+            }
+            ShapeType::Capsule | ShapeType::Triangle | ShapeType::RoundedRect | ShapeType::Polygon(_) => {
+                draw.polygon()
+                    .color(self.color.with_alpha(alpha))
+                    .stroke_color(self.stroke_color.with_alpha(alpha))
+                    .stroke_weight(self.stroke_weight)
+                    .points(self.outline())
+                    .x_y(pos.x, pos.y)
+                    .rotate(rot);
+            }
+        }
+    }
+
+    /// The points around the shapes that are drawn as polygons, relative to the center
+    fn outline(&self) -> Vec<Point2> {
+        match &self.shape {
+            ShapeType::Capsule => {
+                let radius = self.width.min(self.height);
+                rounded_corners(self.width - radius, self.height - radius, radius)
+            }
+            ShapeType::Triangle => vec![pt2(0.0, self.height), pt2(self.width, -self.height), pt2(-self.width, -self.height)],
+            ShapeType::RoundedRect => {
+                let radius = self.radius.clamp(0.0, self.width.min(self.height));
+                rounded_corners(self.width - radius, self.height - radius, radius)
+            }
+            ShapeType::Polygon(points) => points.iter().map(|[x, y]| pt2(x * self.width, y * self.height)).collect(),
+            ShapeType::Circle | ShapeType::Rect | ShapeType::Ring | ShapeType::Arc | ShapeType::HollowRect
+                | ShapeType::HollowPolygon(_) | ShapeType::Polyline(_) => vec![]
         }
     }
 }
 
+/// A quarter circle with the radius around each corner of a rect with the half size, counterclockwise
+#[cfg(feature = "window")]
+fn rounded_corners(half_width: f32, half_height: f32, radius: f32) -> Vec<Point2> {
+    let corners = [(half_width, half_height), (-half_width, half_height), (-half_width, -half_height), (half_width, -half_height)];
+
+    corners.into_iter().enumerate().flat_map(|(i, (x, y))| (0..=8).map(move |step| {
+        let radian = (i as f32 * 90.0 + step as f32 * 90.0 / 8.0).to_radians();
+        pt2(x + radian.cos() * radius, y + radian.sin() * radius)
+    })).collect()
+}
+
+/// Only drawn with the window, but kept without it so the trail spacing stays the same
 #[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "window"), allow(dead_code))]
 struct TrailObject {
     drawing: ObjectDrawing,
     pos: Translation<f32>,
     rot: f32
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Object {
     drawing: ObjectDrawing,
 
@@ -308,10 +754,32 @@ pub struct Object {
     frames_per_trail_obj: Option<u128>,
     trail_objs: Vec<TrailObject>,
 
+    id: u32,
+    tags: Vec<String>,
+    lifetime: Option<f32>,
+    lifetime_frames: Option<u128>,
+    fade_out: f32,
+    /// Seconds of simulation time since the object was added
+    age: f32,
+    /// Frames since the object was added
+    frames: u128,
+
     bounciness: f32,
+    friction: f32,
+    density: f32,
+    mass: Option<f32>,
+    bounciness_combine: CoefficientCombineRule,
+    friction_combine: CoefficientCombineRule,
+    sensor: bool,
 
     update_fn: Option<Function>,
     hit_fn: Option<Function>,
+    hit_end_fn: Option<Function>,
+    touching_fn: Option<Function>,
+
+    /// The 'update' function calls 'wait', so it runs as a coroutine that can continue on a later frame
+    update_waits: bool,
+    update_coroutine: Option<Coroutine>,
 
     handle: RigidBodyHandle,
 
@@ -319,9 +787,62 @@ pub struct Object {
 }
 
 impl Object {
-    pub fn update(&mut self, physics: &mut Physics, elapsed_frames: u128) {
+    /// Give the object the id scripts use to refer to it
+    pub fn with_id(mut self, id: u32) -> Object {
+        self.id = id;
+        self
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn handle(&self) -> RigidBodyHandle {
+        self.handle
+    }
+
+    /// Whether the object has a 'hit_end' function
+    pub fn has_hit_end(&self) -> bool {
+        self.hit_end_fn.is_some()
+    }
+
+    /// Whether the object has a 'touching' function
+    pub fn has_touching(&self) -> bool {
+        self.touching_fn.is_some()
+    }
+
+    /// Whether the object lived longer than its lifetime in seconds or frames
+    pub fn expired(&self) -> bool {
+        self.lifetime.is_some_and(|lifetime| self.age >= lifetime)
+            || self.lifetime_frames.is_some_and(|lifetime_frames| self.frames >= lifetime_frames)
+    }
+
+    /// The seconds until the object expires, if it has a lifetime
+    fn remaining(&self, dt: f32) -> Option<f32> {
+        let seconds = self.lifetime.map(|lifetime| lifetime - self.age);
+        let frames = self.lifetime_frames.map(|lifetime_frames| lifetime_frames.saturating_sub(self.frames) as f32 * dt);
+
+        match (seconds, frames) {
+            (Some(seconds), Some(frames)) => Some(seconds.min(frames)),
+            (seconds, frames) => seconds.or(frames)
+        }
+    }
+
+    /// The opacity of the object, which goes from 1 to 0 during the fade out at the end of its lifetime
+    pub fn alpha(&self, dt: f32) -> f32 {
+        match self.remaining(dt) {
+            Some(remaining) if self.fade_out > 0.0 => (remaining / self.fade_out).clamp(0.0, 1.0),
+            _ => 1.0
+        }
+    }
+
+    /// Returns whether the 'update' function ran and could have changed the object
+    pub fn update(&mut self, physics: &mut Physics) -> bool {
+        self.age += physics.dt();
+
+        // Objects spawned later start their trail from their own first frame
         if let Some(frames_req) = self.frames_per_trail_obj {
-            if elapsed_frames / frames_req > self.trail_objs.len() as u128 {
+            if self.frames / frames_req > self.trail_objs.len() as u128 {
                 let (pos, rot) = self.get_pos_and_rot(physics);
                 let drawing = self.drawing.clone();
 
@@ -332,24 +853,48 @@ impl Object {
                 });
             }
         }
+        self.frames += 1;
 
-        let object_map = self.to_map(physics);
-        let object = RuntimeValue::Object(object_map);
+        if self.update_waits {
+            self.update_coroutine(physics)
+        } else {
+            self.call(physics, |object| &mut object.update_fn, vec![])
+        }
+    }
+
+    /// Resume the 'update' function when it's done waiting, or start it again when it finished
+    fn update_coroutine(&mut self, physics: &mut Physics) -> bool {
+        if let Some(coroutine) = &mut self.update_coroutine {
+            if !coroutine.tick(physics.dt()) {
+                return false
+            }
+        }
 
-        let func = match &mut self.update_fn {
-            Some(func) => func,
-            None => return
+        let object = self.to_map(physics);
+        let step = match (&mut self.update_coroutine, &self.update_fn) {
+            (Some(coroutine), _) => coroutine.resume(object),
+            (None, Some(func)) => {
+                let (coroutine, step) = Coroutine::start(func.clone(), object);
+                self.update_coroutine = Some(coroutine);
+                step
+            }
+            (None, None) => return false
         };
 
-        let new_map = match eval_runtime_object_expr(object, func) {
-            RuntimeValue::Object(map) => map,
-            _ => panic!("Invalid object")
+        let new_map = match step {
+            CoroutineStep::Suspended(map) => map,
+            CoroutineStep::Finished(map) => {
+                self.update_coroutine = None;
+                map
+            }
         };
 
         self.update_map(new_map, physics);
+        true
     }
 
-    pub fn hit(&mut self, assets_path: &Path, physics: &mut Physics, audio_stream: &mut nannou_audio::Stream<Audio>) {
+    #[cfg(feature = "audio")]
+    pub fn play_hit_note(&self, assets_path: &std::path::Path, audio_stream: &mut nannou_audio::Stream<super::Audio>) {
         let mut note_path = assets_path.join("notes");
         note_path.push(self.hit_note.clone() + ".wav");
 
@@ -357,21 +902,40 @@ impl Object {
         let volume = self.hit_note_volume;
 
         audio_stream.send(move |audio| audio.play_note(note, volume)).expect("Failed to send to audio stream");
+    }
 
-        let object_map = self.to_map(physics);
-        let object = RuntimeValue::Object(object_map);
+    /// Call the hit function with the property map of the other object and the details of the contact
+    pub fn hit(&mut self, physics: &mut Physics, other: RuntimeValue, contact: RuntimeValue) -> bool {
+        self.call(physics, |object| &mut object.hit_fn, vec![other, contact])
+    }
 
-        let func = match &mut self.hit_fn {
-            Some(func) => func,
-            None => return
-        };
+    /// Call the hit_end function with the property map of the object it stopped touching
+    pub fn hit_end(&mut self, physics: &mut Physics, other: RuntimeValue) -> bool {
+        self.call(physics, |object| &mut object.hit_end_fn, vec![other])
+    }
+
+    /// Call the touching function with the property map of an object it's touching
+    pub fn touching(&mut self, physics: &mut Physics, other: RuntimeValue) -> bool {
+        self.call(physics, |object| &mut object.touching_fn, vec![other])
+    }
 
-        let new_map = match eval_runtime_object_expr(object, func) {
+    /// Call the function with the object and apply the changes, returns whether the object has the function
+    fn call(&mut self, physics: &mut Physics, callback: fn(&mut Object) -> &mut Option<Function>, args: Vec<RuntimeValue>) -> bool {
+        // Most objects don't have every callback, so their map is only made when it's needed
+        if callback(self).is_none() {
+            return false
+        }
+
+        let object = RuntimeValue::Object(self.to_map(physics));
+        let func = callback(self).as_mut().expect("The callback was checked above");
+
+        let new_map = match eval_runtime_object_expr(object, args, func) {
             RuntimeValue::Object(map) => map,
             _ => panic!("Invalid object")
         };
 
         self.update_map(new_map, physics);
+        true
     }
 
     pub fn to_map(&self, physics: &Physics) -> HashMap<String, RuntimeValue> {
@@ -381,17 +945,42 @@ impl Object {
         let pos = rigidbody.position().translation;
         let gravity = rigidbody.gravity_scale();
 
-        map.insert("shape".to_string(), RuntimeValue::Shape(self.drawing.shape));
+        map.insert("shape".to_string(), RuntimeValue::Shape(self.drawing.shape.clone()));
 
         map.insert("x".to_string(), RuntimeValue::Number(pos.x));
         map.insert("y".to_string(), RuntimeValue::Number(pos.y));
+        map.insert("rotation".to_string(), RuntimeValue::Number(rigidbody.rotation().angle().to_degrees()));
+        map.insert("vx".to_string(), RuntimeValue::Number(rigidbody.linvel().x));
+        map.insert("vy".to_string(), RuntimeValue::Number(rigidbody.linvel().y));
+        map.insert("direction".to_string(), RuntimeValue::Number(direction(*rigidbody.linvel())));
+        map.insert("angular_velocity".to_string(), RuntimeValue::Number(rigidbody.angvel().to_degrees()));
 
         map.insert("width".to_string(), RuntimeValue::Number(self.drawing.width));
         map.insert("height".to_string(), RuntimeValue::Number(self.drawing.height));
         map.insert("size".to_string(), RuntimeValue::Number(self.drawing.width));
+        map.insert("radius".to_string(), RuntimeValue::Number(self.drawing.radius));
+        map.insert("start_angle".to_string(), RuntimeValue::Number(self.drawing.start_angle));
+        map.insert("end_angle".to_string(), RuntimeValue::Number(self.drawing.end_angle));
+        map.insert("gap".to_string(), RuntimeValue::Number(self.gap()));
 
         map.insert("gravity".to_string(), RuntimeValue::Number(gravity));
         map.insert("bounciness".to_string(), RuntimeValue::Number(self.bounciness));
+        // Fixed objects that were moved by a script are kinematic in the physics but still fixed for the script
+        map.insert("fixed".to_string(), RuntimeValue::Boolean(matches!(rigidbody.body_type(), RigidBodyType::Fixed | RigidBodyType::KinematicPositionBased)));
+        map.insert("kinematic".to_string(), RuntimeValue::Boolean(rigidbody.body_type() == RigidBodyType::KinematicVelocityBased));
+        map.insert("sensor".to_string(), RuntimeValue::Boolean(self.sensor));
+
+        map.insert("friction".to_string(), RuntimeValue::Number(self.friction));
+        map.insert("density".to_string(), RuntimeValue::Number(self.density));
+        // Only a fixed mass is in the map, so copies of other objects get their mass from the density
+        if let Some(mass) = self.mass {
+            map.insert("mass".to_string(), RuntimeValue::Number(mass));
+        }
+        map.insert("linear_damping".to_string(), RuntimeValue::Number(rigidbody.linear_damping()));
+        map.insert("angular_damping".to_string(), RuntimeValue::Number(rigidbody.angular_damping()));
+        map.insert("bounciness_combine".to_string(), RuntimeValue::String(combine_rule_name(self.bounciness_combine).to_string()));
+        map.insert("friction_combine".to_string(), RuntimeValue::String(combine_rule_name(self.friction_combine).to_string()));
+        map.insert("lock_rotation".to_string(), RuntimeValue::Boolean(rigidbody.locked_axes().contains(LockedAxes::ROTATION_LOCKED)));
 
         map.insert("color".to_string(), RuntimeValue::Color(self.drawing.color));
         map.insert("stroke_color".to_string(), RuntimeValue::Color(self.drawing.stroke_color));
@@ -400,6 +989,17 @@ impl Object {
         map.insert("hit_note".to_string(), RuntimeValue::Note(self.hit_note.clone()));
         map.insert("hit_note_volume".to_string(), RuntimeValue::Number(self.hit_note_volume));
 
+        map.insert("id".to_string(), RuntimeValue::Number(self.id as f32));
+        map.insert("tags".to_string(), RuntimeValue::List(self.tags.iter().cloned().map(RuntimeValue::String).collect()));
+        map.insert("age".to_string(), RuntimeValue::Number(self.age));
+        map.insert("fade_out".to_string(), RuntimeValue::Number(self.fade_out));
+        if let Some(lifetime) = self.lifetime {
+            map.insert("lifetime".to_string(), RuntimeValue::Number(lifetime));
+        }
+        if let Some(lifetime_frames) = self.lifetime_frames {
+            map.insert("lifetime_frames".to_string(), RuntimeValue::Number(lifetime_frames as f32));
+        }
+
         for (key, value) in self.others.clone() {
             map.insert(key, value);
         }
@@ -408,10 +1008,25 @@ impl Object {
     }
 
     pub fn update_map(&mut self, new_map: HashMap<String, RuntimeValue>, physics: &mut Physics) {
+        let old_mass = self.mass;
+
         let rigidbody = physics.bodies.get_mut(self.handle).expect("Failed to get rigidbody");
         let mut pos = *rigidbody.position();
+        let mut vel = *rigidbody.linvel();
+        let mut angvel = rigidbody.angvel();
+        let mut speed = None;
+        let mut new_direction = None;
 
         let wake_up = !rigidbody.is_sleeping();
+        let old_settings = self.collider_settings();
+        let old_rotation = rigidbody.rotation().angle().to_degrees();
+        let old_direction = direction(vel);
+        let old_angular_velocity = angvel.to_degrees();
+        let old_gap = self.gap();
+        let mut fixed = matches!(rigidbody.body_type(), RigidBodyType::Fixed | RigidBodyType::KinematicPositionBased);
+        let mut kinematic = rigidbody.body_type() == RigidBodyType::KinematicVelocityBased;
+
+        self.others.retain(|key, _| new_map.contains_key(key));
 
         for (key, value) in new_map {
             match (key.as_str(), value) {
@@ -419,16 +1034,64 @@ impl Object {
 
                 ("x", RuntimeValue::Number(number)) => pos.translation.x = number,
                 ("y", RuntimeValue::Number(number)) => pos.translation.y = number,
+                ("rotation", RuntimeValue::Number(number)) => if number != old_rotation {
+                    pos.rotation = Rotation::new(number.to_radians());
+                },
+                // 'speed' is only in the map when a script set it, 'direction' always is
+                ("speed", RuntimeValue::Number(number)) => speed = Some(number),
+                ("vx", RuntimeValue::Number(number)) => vel.x = number,
+                ("vy", RuntimeValue::Number(number)) => vel.y = number,
+                ("direction", RuntimeValue::Number(number)) => if number != old_direction {
+                    new_direction = Some(number);
+                },
+                ("angular_velocity", RuntimeValue::Number(number)) => if number != old_angular_velocity {
+                    angvel = number.to_radians();
+                },
 
-                ("width", RuntimeValue::Number(number)) => self.drawing.width = number,
-                ("height", RuntimeValue::Number(number)) => self.drawing.height = number,
-                ("size", RuntimeValue::Number(number)) => {
+                // 'size', 'width' and 'height' are always in the map, so only the changed ones are used
+                ("width", RuntimeValue::Number(number)) => if number != old_settings.width {
                     self.drawing.width = number;
+                },
+                ("height", RuntimeValue::Number(number)) => if number != old_settings.height {
                     self.drawing.height = number;
                 },
+                ("size", RuntimeValue::Number(number)) => if number != old_settings.width {
+                    self.drawing.width = number;
+                    self.drawing.height = number;
+                },
+                ("radius", RuntimeValue::Number(number)) => self.drawing.radius = number,
+                // 'gap' is always in the map like 'size', so it's only used when it changed
+                ("start_angle", RuntimeValue::Number(number)) => if number != old_settings.start_angle {
+                    self.drawing.start_angle = number;
+                },
+                ("end_angle", RuntimeValue::Number(number)) => if number != old_settings.end_angle {
+                    self.drawing.end_angle = number;
+                },
+                ("gap", RuntimeValue::Number(number)) => if number != old_gap {
+                    self.drawing.start_angle = number / 2.0;
+                    self.drawing.end_angle = 360.0 - number / 2.0;
+                },
 
                 ("gravity", RuntimeValue::Number(number)) => rigidbody.set_gravity_scale(number, wake_up),
                 ("bounciness", RuntimeValue::Number(number)) => self.bounciness = number,
+                ("fixed", RuntimeValue::Boolean(boolean)) => fixed = boolean,
+                ("kinematic", RuntimeValue::Boolean(boolean)) => kinematic = boolean,
+                ("sensor", RuntimeValue::Boolean(boolean)) => self.sensor = boolean,
+
+                ("friction", RuntimeValue::Number(number)) => self.friction = number,
+                // 'density' is always in the map and a fixed 'mass' stays in it after the density changes, so only the changed one is used
+                ("density", RuntimeValue::Number(number)) => if number != old_settings.density {
+                    self.density = number;
+                    self.mass = None;
+                },
+                ("mass", RuntimeValue::Number(number)) => if Some(number) != old_mass {
+                    self.mass = Some(number);
+                },
+                ("linear_damping", RuntimeValue::Number(number)) => rigidbody.set_linear_damping(number),
+                ("angular_damping", RuntimeValue::Number(number)) => rigidbody.set_angular_damping(number),
+                ("bounciness_combine", RuntimeValue::String(rule)) => self.bounciness_combine = combine_rule(&rule),
+                ("friction_combine", RuntimeValue::String(rule)) => self.friction_combine = combine_rule(&rule),
+                ("lock_rotation", RuntimeValue::Boolean(boolean)) => rigidbody.lock_rotations(boolean, wake_up),
 
                 ("color", RuntimeValue::Color(color)) => self.drawing.color = color,
                 ("stroke_color", RuntimeValue::Color(color)) => self.drawing.stroke_color = color,
@@ -437,53 +1100,109 @@ impl Object {
                 ("hit_note", RuntimeValue::Note(note)) => self.hit_note = note,
                 ("hit_note_volume", RuntimeValue::Number(number)) => self.hit_note_volume = number,
 
+                ("tags", RuntimeValue::List(tags)) => self.tags = tag_names(tags),
+                ("lifetime", RuntimeValue::Number(number)) => self.lifetime = Some(number),
+                ("lifetime_frames", RuntimeValue::Number(number)) => self.lifetime_frames = Some(number.max(0.0) as u128),
+                ("fade_out", RuntimeValue::Number(number)) => self.fade_out = number,
+
+                ("id", RuntimeValue::Number(number)) if number == self.id as f32 => {},
+                ("id", _) => panic!("The id of an object can't be changed"),
+                ("age", RuntimeValue::Number(_)) => {},
+
+                (key, value) if PROPERTIES.contains(&key) => panic!("Invalid key-value pair to update object: {}-{}", key, value),
                 (key, value) => {
-                    if self.others.contains_key(key) {
-                        self.others.insert(key.to_string(), value);
-                    } else {
-                        panic!("Invalid key-value pair to update object: {}-{}", key, value);
-                    }
+                    self.others.insert(key.to_string(), value);
                 }
             }
         }
 
-        rigidbody.set_position(pos, wake_up);
-        self.update_shape(physics);
+        // The speed and direction are applied after 'vx' and 'vy', so the order of the keys doesn't matter
+        if speed.is_some() || new_direction.is_some() {
+            vel = polar(speed.unwrap_or(vel.norm()), new_direction.unwrap_or(direction(vel)));
+        }
+
+        // Like when the object is built, kinematic wins over fixed and lines are always fixed. Fixed objects that were
+        // moved before stay kinematic
+        let body_type = if kinematic {
+            RigidBodyType::KinematicVelocityBased
+        } else if fixed || matches!(self.drawing.shape, ShapeType::Polyline(_)) {
+            match rigidbody.body_type() {
+                RigidBodyType::KinematicPositionBased => RigidBodyType::KinematicPositionBased,
+                _ => RigidBodyType::Fixed
+            }
+        } else {
+            RigidBodyType::Dynamic
+        };
+        if body_type != rigidbody.body_type() {
+            rigidbody.set_body_type(body_type, true);
+        }
+
+        // Fixed objects only become kinematic when a script moves them, so they push the objects they hit instead of jumping through them
+        if rigidbody.body_type() == RigidBodyType::Fixed && pos != *rigidbody.position() {
+            rigidbody.set_body_type(RigidBodyType::KinematicPositionBased, true);
+        }
+
+        if rigidbody.body_type() == RigidBodyType::KinematicPositionBased {
+            rigidbody.set_next_kinematic_position(pos);
+        } else {
+            rigidbody.set_position(pos, wake_up);
+            rigidbody.set_linvel(vel, wake_up);
+            rigidbody.set_angvel(angvel, wake_up);
+        }
+
+        // Rebuilding the colliders would make every contact start again, so only a new shape does that
+        let settings = self.collider_settings();
+        if !settings.same_shape(&old_settings) {
+            self.update_shape(physics);
+        } else if settings != old_settings {
+            physics.set_material(self.handle, &settings);
+        }
     }
 
     pub fn update_shape(&mut self, physics: &mut Physics) {
         let rigidbody = physics.bodies.get(self.handle).expect("Failed to get rigidbody").clone();
 
         physics.remove_colliders(rigidbody.colliders());
-        physics.add_collider(
-            self.handle,
-            self.drawing.shape,
-            self.bounciness,
-            self.drawing.width,
-            self.drawing.height,
-            self.drawing.stroke_weight
-        );
+        physics.add_collider(self.handle, &self.collider_settings());
     }
 
-    pub fn test_collider(&self, physics: &Physics, collider: ColliderHandle) -> bool {
-        let rigidbody = match physics.bodies.get(self.handle) {
-            Some(rb) => rb,
-            None => panic!("Object doesn't have associated handle")
-        };
+    /// The degrees of an arc that are open
+    fn gap(&self) -> f32 {
+        360.0 - (self.drawing.end_angle - self.drawing.start_angle)
+    }
 
-        rigidbody.colliders().contains(&collider)
+    pub fn collider_settings(&self) -> ColliderSettings {
+        ColliderSettings {
+            shape: self.drawing.shape.clone(),
+            width: self.drawing.width,
+            height: self.drawing.height,
+            radius: self.drawing.radius,
+            start_angle: self.drawing.start_angle,
+            end_angle: self.drawing.end_angle,
+            stroke_weight: self.drawing.stroke_weight,
+            bounciness: self.bounciness,
+            friction: self.friction,
+            density: self.density,
+            mass: self.mass,
+            bounciness_combine: self.bounciness_combine,
+            friction_combine: self.friction_combine,
+            sensor: self.sensor
+        }
     }
 
+    #[cfg(feature = "window")]
     pub fn draw(&self, draw: &Draw, physics: &Physics) {
+        let alpha = self.alpha(physics.dt());
+
         for TrailObject { drawing, pos, rot } in &self.trail_objs {
-            drawing.draw(draw, *pos, *rot);
+            drawing.draw(draw, *pos, *rot, alpha);
         }
 
         let (pos, rot) = self.get_pos_and_rot(physics);
-        self.drawing.draw(draw, pos, rot);
+        self.drawing.draw(draw, pos, rot, alpha);
     }
 
-    fn get_pos_and_rot(&self, physics: &Physics) -> (Translation<f32>, f32) {
+    pub fn get_pos_and_rot(&self, physics: &Physics) -> (Translation<f32>, f32) {
         let rigidbody = match physics.bodies.get(self.handle) {
             Some(rb) => rb,
             None => panic!("Object doesn't have associated handle")
@@ -1,10 +1,133 @@
-use nannou::math::deg_to_rad;
-use rapier2d::prelude::*;
+use rapier2d::{parry::transformation, prelude::*};
 
 use crate::frontend::ShapeType;
 
 use super::ObjectBuilder;
 
+/// Where and how hard two colliders touched, the normal points away from the first collider
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactDetails {
+    pub point: Point<Real>,
+    pub normal: Vector<Real>,
+    pub relative_speed: Real,
+    pub impulse: Real
+}
+
+impl ContactDetails {
+    /// The same contact as seen from the second collider
+    pub fn flipped(self) -> Self {
+        Self {
+            normal: -self.normal,
+            ..self
+        }
+    }
+}
+
+/// Everything that determines the colliders of an object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColliderSettings {
+    pub shape: ShapeType,
+    pub width: f32,
+    pub height: f32,
+    /// The radius of the corners of a rounded rect
+    pub radius: f32,
+    /// Where an arc starts and ends in degrees counterclockwise from the right
+    pub start_angle: f32,
+    pub end_angle: f32,
+    pub stroke_weight: f32,
+    pub bounciness: f32,
+    pub friction: f32,
+    pub density: f32,
+    /// Replaces the mass that follows from the density
+    pub mass: Option<f32>,
+    pub bounciness_combine: CoefficientCombineRule,
+    pub friction_combine: CoefficientCombineRule,
+    pub sensor: bool
+}
+
+impl ColliderSettings {
+    /// Whether both settings give colliders of the same shape, which only differ in their material
+    pub fn same_shape(&self, other: &ColliderSettings) -> bool {
+        self.shape == other.shape
+            && self.width == other.width
+            && self.height == other.height
+            && self.radius == other.radius
+            && self.start_angle == other.start_angle
+            && self.end_angle == other.end_angle
+            && self.stroke_weight == other.stroke_weight
+    }
+}
+
+/// The bounciness or friction of a contact, the rule of the two that comes later in 'CoefficientCombineRule' is used like in rapier
+fn combine(coefficient1: Real, rule1: CoefficientCombineRule, coefficient2: Real, rule2: CoefficientCombineRule) -> Real {
+    match (rule1 as u8).max(rule2 as u8) {
+        0 => (coefficient1 + coefficient2) / 2.0,
+        1 => coefficient1.min(coefficient2),
+        2 => coefficient1 * coefficient2,
+        _ => coefficient1.max(coefficient2)
+    }
+}
+
+/// How a joint connects two bodies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JointKind {
+    /// Keeps the bodies in place relative to each other
+    Fixed,
+    /// Lets the bodies rotate around the anchor
+    Revolute,
+    /// Lets the bodies slide along the axis, optionally only between the limits
+    Prismatic { axis: Vector<Real>, limits: Option<[Real; 2]> },
+    /// Keeps the centers of the bodies at most the length apart
+    Rope { length: Real },
+    /// Pulls the centers of the bodies back to their distance when it was added
+    Spring { stiffness: Real, damping: Real }
+}
+
+/// The angles in radians of the points along an arc, at most the step in degrees apart
+pub fn arc_angles(start_angle: f32, end_angle: f32, step: f32) -> impl Iterator<Item = f32> {
+    let span = (end_angle - start_angle).clamp(0.0, 360.0);
+    let segments = (span / step).ceil() as u32;
+
+    (0..=segments).map(move |i| (start_angle + span * i as f32 / segments.max(1) as f32).to_radians())
+}
+
+/// The closed outline of a hollow shape, scaled by the width and height of the object
+pub fn hollow_outline(shape: &ShapeType) -> Vec<[f32; 2]> {
+    let mut points = match shape {
+        ShapeType::HollowRect => vec![[1.0, 1.0], [-1.0, 1.0], [-1.0, -1.0], [1.0, -1.0]],
+        ShapeType::HollowPolygon(points) => points.clone(),
+        _ => vec![]
+    };
+
+    if let Some(&first) = points.first() {
+        points.push(first);
+    }
+
+    points
+}
+
+/// The degrees between the points of the collider of a ring, the straight lines between them are at most a quarter pixel
+/// inside the circle. Fewer points make rings much faster to collide with each other
+fn collider_step(radius: f32) -> f32 {
+    (2.0 * (1.0 - 0.25 / radius.max(0.25)).acos().to_degrees()).clamp(2.0, 30.0)
+}
+
+/// Capsules between the points as a single collider as thick as the line that is drawn, so a bounce is a single contact
+fn thick_polyline(vertices: &[Point<Real>], thickness: f32) -> ColliderBuilder {
+    let shapes = vertices.windows(2)
+        .map(|pair| (Isometry::identity(), SharedShape::capsule(pair[0], pair[1], thickness / 2.0)))
+        .collect();
+
+    ColliderBuilder::compound(shapes)
+}
+
+/// A collision that started or stopped during a step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContactEvent {
+    Started(ColliderHandle, ColliderHandle, ContactDetails),
+    Stopped(ColliderHandle, ColliderHandle)
+}
+
 pub struct Physics {
     pipeline: PhysicsPipeline,
     gravity: Vector<f32>,
@@ -19,6 +142,12 @@ pub struct Physics {
     ccd_solver: CCDSolver,
 }
 
+impl Default for Physics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Physics {
     pub fn new() -> Self {
         Self {
@@ -36,68 +165,317 @@ impl Physics {
         }
     }
 
+    /// Change the material of the colliders of a body in place, so the contacts they have stay
+    pub fn set_material(&mut self, handle: RigidBodyHandle, settings: &ColliderSettings) {
+        let colliders = self.bodies.get(handle).expect("Invalid handle").colliders().to_vec();
+
+        for collider in colliders {
+            let collider = self.colliders.get_mut(collider).expect("Invalid collider handle");
+
+            match settings.mass {
+                Some(mass) => collider.set_mass(mass),
+                None => collider.set_density(settings.density)
+            }
+            collider.set_restitution(settings.bounciness);
+            collider.set_restitution_combine_rule(settings.bounciness_combine);
+            collider.set_friction(settings.friction);
+            collider.set_friction_combine_rule(settings.friction_combine);
+            collider.set_sensor(settings.sensor);
+        }
+    }
+
     pub fn remove_colliders(&mut self, colliders: &[ColliderHandle]) {
         for collider in colliders {
             self.colliders.remove(*collider, &mut self.island_manager, &mut self.bodies, true);
         }
     }
 
-    pub fn add_collider(&mut self, handle: RigidBodyHandle, shape: ShapeType, bounciness: f32, width: f32, height: f32, stroke_weight: f32) {
+    /// Remove a rigid body together with its colliders
+    pub fn remove(&mut self, handle: RigidBodyHandle) {
+        self.bodies.remove(handle, &mut self.island_manager, &mut self.colliders, &mut self.impulse_joints, &mut self.multibody_joints, true);
+    }
+
+    /// Connect two bodies, the anchor is in world coordinates and halfway between the bodies by default.
+    /// Ropes and springs are always attached to the centers of the bodies
+    pub fn add_joint(&mut self, body1: RigidBodyHandle, body2: RigidBodyHandle, kind: JointKind, anchor: Option<Point<Real>>) -> ImpulseJointHandle {
+        let position1 = *self.bodies.get(body1).expect("Invalid rigidbody handle").position();
+        let position2 = *self.bodies.get(body2).expect("Invalid rigidbody handle").position();
+
+        let anchor = anchor.unwrap_or_else(|| ((position1.translation.vector + position2.translation.vector) / 2.0).into());
+        let local_anchor1 = position1.inverse_transform_point(&anchor);
+        let local_anchor2 = position2.inverse_transform_point(&anchor);
+
+        let joint: GenericJoint = match kind {
+            JointKind::Fixed => {
+                let frame = Isometry::translation(anchor.x, anchor.y);
+                FixedJointBuilder::new()
+                    .local_frame1(position1.inverse() * frame)
+                    .local_frame2(position2.inverse() * frame)
+                    .into()
+            }
+            JointKind::Revolute => RevoluteJointBuilder::new().local_anchor1(local_anchor1).local_anchor2(local_anchor2).into(),
+            JointKind::Prismatic { axis, limits } => {
+                let axis = UnitVector::try_new(axis, 0.0).unwrap_or_else(|| panic!("Invalid joint axis: [{}, {}]", axis.x, axis.y));
+                let mut builder = PrismaticJointBuilder::new(axis)
+                    .local_anchor1(local_anchor1)
+                    .local_anchor2(local_anchor2)
+                    .local_axis1(position1.rotation.inverse() * axis)
+                    .local_axis2(position2.rotation.inverse() * axis);
+
+                if let Some(limits) = limits {
+                    builder = builder.limits(limits);
+                }
+
+                builder.into()
+            }
+            JointKind::Rope { length } => RopeJointBuilder::new(length).into(),
+            JointKind::Spring { stiffness, damping } => {
+                let rest_length = (position2.translation.vector - position1.translation.vector).norm();
+                SpringJointBuilder::new(rest_length, stiffness, damping).into()
+            }
+        };
+
+        self.impulse_joints.insert(body1, body2, joint, true)
+    }
+
+    pub fn remove_joint(&mut self, handle: ImpulseJointHandle) {
+        self.impulse_joints.remove(handle, true);
+    }
+
+    /// The force the joint applied along its linear axes during the last step
+    pub fn joint_force(&self, handle: ImpulseJointHandle) -> Real {
+        let Some(joint) = self.impulse_joints.get(handle) else {
+            return 0.0
+        };
+
+        // Locked axes, limits like the length of ropes and motors like springs store their impulses separately
+        let impulse = |axis: usize| joint.impulses[axis] + joint.data.limits[axis].impulse + joint.data.motors[axis].impulse;
+        vector![impulse(0), impulse(1)].norm() / self.dt()
+    }
+
+    /// The center of the first body, both anchors and the center of the second body in world coordinates
+    pub fn joint_points(&self, handle: ImpulseJointHandle) -> Option<[Point<Real>; 4]> {
+        let joint = self.impulse_joints.get(handle)?;
+        let position1 = self.bodies.get(joint.body1)?.position();
+        let position2 = self.bodies.get(joint.body2)?.position();
+
+        Some([
+            position1.translation.vector.into(),
+            position1 * joint.data.local_anchor1(),
+            position2 * joint.data.local_anchor2(),
+            position2.translation.vector.into()
+        ])
+    }
+
+    /// The simulation time of one step in seconds
+    pub fn dt(&self) -> f32 {
+        self.integration_parameters.dt
+    }
+
+    /// Change the momentum of a body, at its center or at a point in world coordinates
+    pub fn apply_impulse(&mut self, handle: RigidBodyHandle, impulse: Vector<Real>, point: Option<Point<Real>>) {
+        let body = self.bodies.get_mut(handle).expect("Invalid handle");
+        match point {
+            Some(point) => body.apply_impulse_at_point(impulse, point, true),
+            None => body.apply_impulse(impulse, true)
+        }
+    }
+
+    pub fn apply_torque_impulse(&mut self, handle: RigidBodyHandle, torque_impulse: Real) {
+        self.bodies.get_mut(handle).expect("Invalid handle").apply_torque_impulse(torque_impulse, true);
+    }
+
+    /// Push every dynamic body within the radius away from the center, the strength is the change in speed at the center and gets weaker towards the edge
+    pub fn explode(&mut self, center: Point<Real>, strength: Real, radius: Real) {
+        for (_, body) in self.bodies.iter_mut().filter(|(_, body)| body.is_dynamic()) {
+            let offset = body.translation() - center.coords;
+            let distance = offset.norm();
+
+            if let Some(direction) = offset.try_normalize(0.0).filter(|_| distance < radius) {
+                let impulse = direction * strength * (1.0 - distance / radius) * body.mass();
+                body.apply_impulse(impulse, true);
+            }
+        }
+    }
+
+    pub fn add_collider(&mut self, handle: RigidBodyHandle, settings: &ColliderSettings) {
+        let stroke_weight = settings.stroke_weight;
         let half_stroke = stroke_weight / 2.0;
-        let width = width + half_stroke;
-        let height = height + half_stroke;
+        let width = settings.width + half_stroke;
+        let height = settings.height + half_stroke;
+
+        let build = |builder: ColliderBuilder| {
+            let builder = match settings.mass {
+                Some(mass) => builder.mass(mass),
+                None => builder.density(settings.density)
+            };
 
-        match shape {
+            builder
+                .restitution(settings.bounciness)
+                .restitution_combine_rule(settings.bounciness_combine)
+                .friction(settings.friction)
+                .friction_combine_rule(settings.friction_combine)
+                .sensor(settings.sensor)
+                .active_events(ActiveEvents::all())
+                .build()
+        };
+
+        match &settings.shape {
             ShapeType::Circle => {
-                let collider = ColliderBuilder::ball(width)
-                    .restitution(bounciness)
-                    .active_events(ActiveEvents::all())
-                    .build();
+                let collider = build(ColliderBuilder::ball(width));
                 self.colliders.insert_with_parent(collider, handle, &mut self.bodies);
             },
             ShapeType::Rect => {
-                let collider = ColliderBuilder::cuboid(width, height)
-                    .restitution(bounciness)
-                    .active_events(ActiveEvents::all())
-                    .build();
+                let collider = build(ColliderBuilder::cuboid(width, height));
                 self.colliders.insert_with_parent(collider, handle, &mut self.bodies);
             },
-            ShapeType::Ring => {
-                let mut vertices = vec![];
+            ShapeType::Arc => {
+                let vertices: Vec<Point<Real>> = arc_angles(settings.start_angle, settings.end_angle, collider_step(settings.width))
+                    .map(|radian| point![radian.cos(), radian.sin()] * settings.width)
+                    .collect();
 
-                for i in (0..=360).step_by(2) {
-                    let radian = deg_to_rad(i as f32);
-                    vertices.push(point![radian.sin(), radian.cos()] * width);
+                if vertices.len() >= 2 {
+                    self.colliders.insert_with_parent(build(thick_polyline(&vertices, stroke_weight)), handle, &mut self.bodies);
                 }
+            },
+            ShapeType::Capsule => {
+                let builder = if width >= height {
+                    ColliderBuilder::capsule_x(width - height, height)
+                } else {
+                    ColliderBuilder::capsule_y(height - width, width)
+                };
+                self.colliders.insert_with_parent(build(builder), handle, &mut self.bodies);
+            }
+            ShapeType::HollowRect | ShapeType::HollowPolygon(_) => {
+                let vertices: Vec<Point<Real>> = hollow_outline(&settings.shape).into_iter()
+                    .map(|[x, y]| point![x * settings.width, y * settings.height])
+                    .collect();
 
-                for point in vertices {
-                    let collider = ColliderBuilder::ball(stroke_weight / 2.0)
-                        .position(point.into())
-                        .restitution(bounciness)
-                        .active_events(ActiveEvents::all())
-                        .build();
+                self.colliders.insert_with_parent(build(thick_polyline(&vertices, stroke_weight)), handle, &mut self.bodies);
+            }
+            ShapeType::Polyline(points) => {
+                let vertices: Vec<Point<Real>> = points.iter().map(|[x, y]| point![*x, *y]).collect();
 
-                    self.colliders.insert_with_parent(collider, handle, &mut self.bodies);
-                }
+                let builder = match vertices.as_slice() {
+                    [start, end] => ColliderBuilder::segment(*start, *end),
+                    _ => ColliderBuilder::polyline(vertices, None)
+                };
+
+                self.colliders.insert_with_parent(build(builder), handle, &mut self.bodies);
+            },
+            ShapeType::Triangle => {
+                let collider = build(ColliderBuilder::triangle(point![0.0, height], point![width, -height], point![-width, -height]));
+                self.colliders.insert_with_parent(collider, handle, &mut self.bodies);
+            },
+            ShapeType::RoundedRect => {
+                let radius = settings.radius.clamp(0.0, settings.width.min(settings.height));
+                let collider = build(ColliderBuilder::round_cuboid(width - radius, height - radius, radius));
+                self.colliders.insert_with_parent(collider, handle, &mut self.bodies);
+            },
+            ShapeType::Ring => {
+                let vertices: Vec<Point<Real>> = arc_angles(0.0, 360.0, collider_step(settings.width))
+                    .map(|radian| point![radian.cos(), radian.sin()] * settings.width)
+                    .collect();
+
+                self.colliders.insert_with_parent(build(thick_polyline(&vertices, stroke_weight)), handle, &mut self.bodies);
+            },
+            ShapeType::Polygon(points) => {
+                let vertices: Vec<Point<Real>> = points.iter().map(|[x, y]| point![x * width, y * height]).collect();
+
+                // Every point of a convex outline is on its hull, concave outlines are split into convex parts
+                let builder = if transformation::convex_hull(&vertices).len() == vertices.len() {
+                    ColliderBuilder::convex_hull(&vertices).expect("Invalid polygon: the points don't enclose an area")
+                } else {
+                    let indices: Vec<[u32; 2]> = (0..vertices.len() as u32).map(|i| [i, (i + 1) % vertices.len() as u32]).collect();
+                    ColliderBuilder::convex_decomposition(&vertices, &indices)
+                };
+
+                self.colliders.insert_with_parent(build(builder), handle, &mut self.bodies);
             }
         }
     }
 
     pub fn add(&mut self, builder: &ObjectBuilder) -> RigidBodyHandle {
-        let rigidbody = if builder.fixed {
+        // Lines don't enclose an area, so they have no mass to move with
+        let rigidbody = if builder.kinematic {
+            RigidBodyBuilder::kinematic_velocity_based()
+        } else if builder.fixed || matches!(builder.shape, ShapeType::Polyline(_)) {
             RigidBodyBuilder::fixed()
         } else {
             RigidBodyBuilder::dynamic()
-        }.ccd_enabled(true).linvel(builder.vel).gravity_scale(builder.gravity).position(builder.pos.into()).build();
+        }.ccd_enabled(true)
+            .linvel(builder.velocity())
+            .angvel(builder.angular_velocity.to_radians())
+            .gravity_scale(builder.gravity)
+            .position(builder.pos.into())
+            .rotation(builder.rotation.to_radians())
+            .linear_damping(builder.linear_damping)
+            .angular_damping(builder.angular_damping)
+            .locked_axes(if builder.lock_rotation { LockedAxes::ROTATION_LOCKED } else { LockedAxes::empty() })
+            .build();
 
         let handle = self.bodies.insert(rigidbody);
 
-        self.add_collider(handle, builder.shape, builder.bounciness, builder.width, builder.height, builder.stroke_weight);
+        self.add_collider(handle, &builder.collider_settings());
 
         handle
     }
 
-    pub fn step(&mut self) -> Vec<(ColliderHandle, ColliderHandle)> {
+    /// Get the contact between two colliders from the narrow phase
+    pub fn contact_details(&self, collider1: ColliderHandle, collider2: ColliderHandle) -> ContactDetails {
+        let position1 = self.colliders.get(collider1).expect("Invalid collider handle").translation();
+        let position2 = self.colliders.get(collider2).expect("Invalid collider handle").translation();
+
+        // Without contact points, assume the colliders touch halfway between their centers
+        let mut point: Point<Real> = ((position1 + position2) / 2.0).into();
+        let mut normal = (position2 - position1).try_normalize(0.0).unwrap_or(vector![0.0, 0.0]);
+        let mut impulse = 0.0;
+
+        if let Some(pair) = self.narrow_phase.contact_pair(collider1, collider2) {
+            if let Some((manifold, contact)) = pair.find_deepest_contact() {
+                let flip = if pair.collider1 == collider1 { 1.0 } else { -1.0 };
+                normal = manifold.data.normal * flip;
+
+                let pair_position1 = self.colliders.get(pair.collider1).expect("Invalid collider handle").position();
+                point = pair_position1 * contact.local_p1;
+            }
+
+            impulse = pair.total_impulse_magnitude();
+        }
+
+        let body = |collider: ColliderHandle| {
+            self.colliders.get(collider)
+                .and_then(|collider| collider.parent())
+                .and_then(|body| self.bodies.get(body))
+        };
+
+        let velocity = |collider| body(collider).map_or(vector![0.0, 0.0], |body| body.velocity_at_point(&point));
+        let inv_mass = |collider| match body(collider) {
+            Some(body) if body.is_dynamic() && body.mass() > 0.0 => 1.0 / body.mass(),
+            _ => 0.0
+        };
+
+        let relative_velocity = velocity(collider1) - velocity(collider2);
+
+        // New contacts haven't been solved yet, so estimate the impulse the solver will apply to bounce them apart
+        let inv_masses = inv_mass(collider1) + inv_mass(collider2);
+        if impulse == 0.0 && inv_masses > 0.0 {
+            let (first, second) = (&self.colliders[collider1], &self.colliders[collider2]);
+            let restitution = combine(first.restitution(), first.restitution_combine_rule(), second.restitution(), second.restitution_combine_rule());
+            let approach_speed = relative_velocity.dot(&normal).max(0.0);
+
+            impulse = (1.0 + restitution) * approach_speed / inv_masses;
+        }
+
+        ContactDetails {
+            point,
+            normal,
+            relative_speed: relative_velocity.norm(),
+            impulse
+        }
+    }
+
+    pub fn step(&mut self) -> Vec<ContactEvent> {
         let hooks = ();
 
         let (collision_send, collision_recv) = crossbeam::channel::unbounded();
@@ -120,14 +498,18 @@ impl Physics {
             &event_handler
         );
 
-        let mut collisions = vec![];
+        let mut events = vec![];
 
         while let Ok(collision_event) = collision_recv.try_recv() {
+            let (collider1, collider2) = (collision_event.collider1(), collision_event.collider2());
+
             if collision_event.started() {
-                collisions.push((collision_event.collider1(), collision_event.collider2()));
+                events.push(ContactEvent::Started(collider1, collider2, self.contact_details(collider1, collider2)));
+            } else {
+                events.push(ContactEvent::Stopped(collider1, collider2));
             }
         }
 
-        collisions
+        events
     }
 }
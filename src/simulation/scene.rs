@@ -0,0 +1,639 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use rapier2d::prelude::{point, vector, ColliderHandle, ImpulseJointHandle, Point, Real, RigidBodyHandle};
+
+use crate::{frontend::Parser, runtime::{evaluate, Color, Environment, ForceRequest, Function, IntoNativeFn, JointRequest, NativeFn, PendingChanges, RuntimeValue}};
+
+use super::{physics::{ContactDetails, ContactEvent, JointKind, Physics}, Object, ObjectBuilder};
+
+/// Two objects that started touching during the last step, stored as the ids of the objects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionEvent {
+    pub first: u32,
+    pub second: u32,
+    pub contact: ContactDetails
+}
+
+/// The state of an object after the last step
+#[derive(Debug, Clone)]
+pub struct ObjectState {
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    pub properties: HashMap<String, RuntimeValue>
+}
+
+/// Configure a scene before its source code is evaluated
+pub struct SceneBuilder {
+    notes: Vec<String>,
+    natives: Vec<NativeFn>
+}
+
+impl Default for SceneBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneBuilder {
+    pub fn new() -> Self {
+        Self {
+            notes: vec![],
+            natives: vec![]
+        }
+    }
+
+    /// The names of the notes that can be used as 'hit_note'
+    pub fn notes(mut self, notes: Vec<String>) -> SceneBuilder {
+        self.notes = notes;
+        self
+    }
+
+    /// Make a Rust function or closure callable from the script
+    pub fn native<Args>(mut self, name: &str, doc: &str, func: impl IntoNativeFn<Args>) -> SceneBuilder {
+        self.natives.push(NativeFn::new(name, doc, func));
+        self
+    }
+
+    /// Evaluate the source code and create all the objects it added
+    pub fn build(self, source_code: String) -> Scene {
+        let mut env = Environment::new_global(self.notes);
+        for native in self.natives {
+            env.declare_var(native.name.clone(), RuntimeValue::NativeFn(native));
+        }
+
+        let physics = Physics::new();
+        set_clock(&env, 0, physics.dt());
+
+        let ast = Parser::new().produce_ast(source_code);
+        evaluate(ast, &mut env);
+
+        let values = match env.lookup_var("objects".to_string()) {
+            RuntimeValue::Objects(objects) => objects,
+            _ => panic!("Invalid 'objects'")
+        };
+
+        let hooks = Hooks::from_env(&env);
+
+        let mut scene = Scene {
+            env,
+            hooks,
+            physics,
+            objects: vec![],
+            indices: HashMap::new(),
+            bodies: HashMap::new(),
+            joints: vec![],
+            num_updates: 0,
+            hits: vec![],
+            collisions: vec![],
+            contacts: HashMap::new()
+        };
+
+        scene.add_objects(&values);
+        scene.sync_world();
+
+        // Every step applies its own changes at the end, this covers the ones made by 'setup'
+        if let Some(setup) = scene.hooks.setup.clone() {
+            scene.run_function(setup, vec![], true);
+        }
+        scene.apply_pending();
+
+        scene
+    }
+}
+
+/// Top level functions that are called by the scene instead of by an object
+#[derive(Default)]
+struct Hooks {
+    /// Called once after the objects are added
+    setup: Option<Function>,
+    /// Called with the frame and the simulation time at the start of every frame
+    on_frame: Option<Function>,
+    /// Called with both objects after their 'hit' functions for every collision
+    on_collision: Option<Function>,
+    /// Called once when the simulation stops
+    on_exit: Option<Function>
+}
+
+impl Hooks {
+    fn from_env(env: &Environment) -> Self {
+        let variables = env.get_variables();
+        let hook = |name: &str| match variables.get(name) {
+            Some(RuntimeValue::Function(func)) => Some(func.clone()),
+            Some(value) => panic!("'{}' has to be a function, got {}", name, value),
+            None => None
+        };
+
+        Self {
+            setup: hook("setup"),
+            on_frame: hook("on_frame"),
+            on_collision: hook("on_collision"),
+            on_exit: hook("on_exit")
+        }
+    }
+}
+
+/// Show the number of frames simulated so far and the simulation time to the scripts, returns the time
+fn set_clock(env: &Environment, frame: u128, dt: f32) -> f32 {
+    let time = frame as f32 * dt;
+
+    env.set_read_only("frame", RuntimeValue::Number(frame as f32));
+    env.set_read_only("time", RuntimeValue::Number(time));
+    env.set_read_only("dt", RuntimeValue::Number(dt));
+
+    time
+}
+
+/// The same key for a pair of colliders regardless of their order
+fn contact_key(collider1: ColliderHandle, collider2: ColliderHandle) -> (ColliderHandle, ColliderHandle) {
+    if collider1.into_raw_parts() <= collider2.into_raw_parts() {
+        (collider1, collider2)
+    } else {
+        (collider2, collider1)
+    }
+}
+
+/// A joint between two objects that breaks when it has to apply more than the break force
+struct Joint {
+    id: u32,
+    handle: ImpulseJointHandle,
+    /// The ids of the objects
+    first: u32,
+    second: u32,
+    break_force: Option<f32>
+}
+
+/// Two numbers given as a list like '[x, y]'
+fn number_pair(name: &str, value: &RuntimeValue) -> [f32; 2] {
+    match value {
+        RuntimeValue::List(values) => match values.as_slice() {
+            [RuntimeValue::Number(first), RuntimeValue::Number(second)] => [*first, *second],
+            _ => panic!("Invalid joint option '{}': expected a list of two numbers, got {}", name, value)
+        },
+        value => panic!("Invalid joint option '{}': expected a list of two numbers, got {}", name, value)
+    }
+}
+
+/// The kind, the anchor and the break force of a joint from the options given to 'joint', 'spring' or 'rope'
+fn joint_settings(options: &HashMap<String, RuntimeValue>) -> (JointKind, Option<Point<Real>>, Option<f32>) {
+    let number = |name: &str| match options.get(name) {
+        Some(RuntimeValue::Number(number)) => Some(*number),
+        Some(value) => panic!("Invalid joint option '{}': expected a number, got {}", name, value),
+        None => None
+    };
+    let required = |name: &str| number(name).unwrap_or_else(|| panic!("Missing joint option '{}'", name));
+
+    let kind = match options.get("kind") {
+        Some(RuntimeValue::String(kind)) => kind.as_str(),
+        Some(value) => panic!("Invalid joint option 'kind': expected a string, got {}", value),
+        None => "revolute"
+    };
+
+    let (kind, allowed): (JointKind, &[&str]) = match kind {
+        "fixed" => (JointKind::Fixed, &["anchor"]),
+        "revolute" => (JointKind::Revolute, &["anchor"]),
+        "prismatic" => {
+            let axis = options.get("axis").map_or([1.0, 0.0], |axis| number_pair("axis", axis));
+            let limits = options.get("limits").map(|limits| number_pair("limits", limits));
+
+            (JointKind::Prismatic { axis: vector![axis[0], axis[1]], limits }, &["anchor", "axis", "limits"])
+        }
+        "rope" => (JointKind::Rope { length: required("length") }, &["length"]),
+        "spring" => (JointKind::Spring { stiffness: required("stiffness"), damping: required("damping") }, &["stiffness", "damping"]),
+        kind => panic!("Invalid joint kind '{}', expected fixed, revolute, prismatic, rope or spring", kind)
+    };
+
+    for name in options.keys() {
+        if name != "kind" && name != "break_force" && !allowed.contains(&name.as_str()) {
+            panic!("Invalid joint option '{}' for this kind of joint", name)
+        }
+    }
+
+    let anchor = options.get("anchor").map(|anchor| {
+        let [x, y] = number_pair("anchor", anchor);
+        point![x, y]
+    });
+
+    (kind, anchor, number("break_force"))
+}
+
+/// The contact as passed to 'hit' functions
+fn contact_value(contact: ContactDetails) -> RuntimeValue {
+    let mut map = HashMap::new();
+
+    map.insert("x".to_string(), RuntimeValue::Number(contact.point.x));
+    map.insert("y".to_string(), RuntimeValue::Number(contact.point.y));
+    map.insert("normal_x".to_string(), RuntimeValue::Number(contact.normal.x));
+    map.insert("normal_y".to_string(), RuntimeValue::Number(contact.normal.y));
+    map.insert("relative_speed".to_string(), RuntimeValue::Number(contact.relative_speed));
+    map.insert("impulse".to_string(), RuntimeValue::Number(contact.impulse));
+
+    RuntimeValue::Object(map)
+}
+
+/// A running phyx simulation, independent of any window or audio
+pub struct Scene {
+    env: Environment,
+    hooks: Hooks,
+    physics: Physics,
+    objects: Vec<Object>,
+    /// The index in 'objects' of every object id
+    indices: HashMap<u32, usize>,
+    /// The id of the object of every body, colliders are found through the body they belong to
+    bodies: HashMap<RigidBodyHandle, u32>,
+    num_updates: u128,
+    hits: Vec<u32>,
+    collisions: Vec<CollisionEvent>,
+    joints: Vec<Joint>,
+    /// The ids of the objects of every pair of colliders that are touching
+    contacts: HashMap<(ColliderHandle, ColliderHandle), (u32, u32)>
+}
+
+impl Scene {
+    pub fn from_source(source_code: String) -> Self {
+        SceneBuilder::new().build(source_code)
+    }
+
+    fn add_objects(&mut self, values: &Vec<RuntimeValue>) {
+        for value in values {
+            if let RuntimeValue::Object(object_map) = value {
+                let id = self.env.next_object_id();
+                self.add_object(id, object_map.clone());
+            } else if let RuntimeValue::Objects(values) = value {
+                self.add_objects(values);
+            } else {
+                panic!("Not an object: {:?}", value);
+            }
+        }
+    }
+
+    fn add_object(&mut self, id: u32, object_map: HashMap<String, RuntimeValue>) {
+        let object = ObjectBuilder::from_map(object_map, &mut self.physics).with_id(id);
+        self.indices.insert(id, self.objects.len());
+        self.bodies.insert(object.handle(), id);
+        self.objects.push(object);
+    }
+
+    /// Connect the objects of the request, joints to objects that are gone are ignored
+    fn add_joint(&mut self, request: JointRequest) {
+        let (kind, anchor, break_force) = joint_settings(&request.options);
+
+        let (Some(first), Some(second)) = (self.index_of(request.first), self.index_of(request.second)) else {
+            return
+        };
+
+        if first == second {
+            panic!("Can't connect object {} to itself", request.first)
+        }
+
+        let handle = self.physics.add_joint(self.objects[first].handle(), self.objects[second].handle(), kind, anchor);
+        self.joints.push(Joint {
+            id: request.id,
+            handle,
+            first: request.first,
+            second: request.second,
+            break_force
+        });
+    }
+
+    fn remove_joint(&mut self, id: u32) {
+        if let Some(index) = self.joints.iter().position(|joint| joint.id == id) {
+            let joint = self.joints.remove(index);
+            self.physics.remove_joint(joint.handle);
+        }
+    }
+
+    /// Remove the joints that had to apply more than their break force during the last step
+    fn break_joints(&mut self) {
+        let broken: Vec<u32> = self.joints.iter()
+            .filter(|joint| joint.break_force.is_some_and(|break_force| self.physics.joint_force(joint.handle) > break_force))
+            .map(|joint| joint.id)
+            .collect();
+
+        for id in broken {
+            self.remove_joint(id);
+        }
+    }
+
+    /// Remove the objects with their bodies, colliders, joints and trails
+    fn remove_objects(&mut self, ids: HashSet<u32>) {
+        for &id in &ids {
+            if let Some(handle) = self.handle_of(id) {
+                self.physics.remove(handle);
+                self.bodies.remove(&handle);
+            }
+        }
+
+        self.objects.retain(|object| !ids.contains(&object.id()));
+        self.indices = self.objects.iter().enumerate().map(|(index, object)| (object.id(), index)).collect();
+        self.joints.retain(|joint| !ids.contains(&joint.first) && !ids.contains(&joint.second));
+
+        // Destroyed objects don't get a 'hit_end' and don't end the contacts of the others
+        self.contacts.retain(|_, (first, second)| !ids.contains(first) && !ids.contains(second));
+    }
+
+    /// Add the spawned objects and joints and remove the destroyed and expired ones
+    fn apply_pending(&mut self) {
+        let PendingChanges { spawned, destroyed, joints, removed_joints, .. } = self.env.take_pending();
+
+        let expired = self.objects.iter().filter(|object| object.expired()).map(|object| object.id());
+        let removed: HashSet<u32> = destroyed.into_iter().chain(expired).collect();
+        let changed = !removed.is_empty() || !spawned.is_empty();
+        if !removed.is_empty() {
+            self.remove_objects(removed);
+        }
+
+        for (id, object_map) in spawned {
+            self.add_object(id, object_map);
+        }
+
+        for request in joints {
+            self.add_joint(request);
+        }
+
+        for id in removed_joints {
+            self.remove_joint(id);
+        }
+
+        // 'world' is synced after the physics step and by the callbacks, so only added and removed objects change it here
+        if changed {
+            self.sync_world();
+        }
+    }
+
+    /// Apply the forces and impulses queued by scripts, the ones for objects that are gone are ignored
+    fn apply_forces(&mut self) {
+        let dt = self.physics.dt();
+
+        for request in self.env.take_forces() {
+            match request {
+                ForceRequest::Force { id, force: [x, y] } => if let Some(handle) = self.handle_of(id) {
+                    self.physics.apply_impulse(handle, vector![x, y] * dt, None);
+                },
+                ForceRequest::Impulse { id, impulse: [x, y] } => if let Some(handle) = self.handle_of(id) {
+                    self.physics.apply_impulse(handle, vector![x, y], None);
+                },
+                ForceRequest::ImpulseAtPoint { id, impulse: [x, y], point: [px, py] } => if let Some(handle) = self.handle_of(id) {
+                    self.physics.apply_impulse(handle, vector![x, y], Some(point![px, py]));
+                },
+                ForceRequest::Torque { id, torque } => if let Some(handle) = self.handle_of(id) {
+                    self.physics.apply_torque_impulse(handle, torque * dt);
+                },
+                ForceRequest::Explosion { center: [x, y], strength, radius } => self.physics.explode(point![x, y], strength, radius)
+            }
+        }
+    }
+
+    /// Show the current state of all objects to 'world'
+    fn sync_world(&self) {
+        self.env.set_world_objects(self.objects.iter().map(|object| object.to_map(&self.physics)).collect());
+    }
+
+    /// Show the changes a callback made to an object to 'world' right away
+    fn sync_object(&self, index: usize) {
+        self.env.set_world_object(index, self.objects[index].to_map(&self.physics));
+    }
+
+    fn index_of(&self, id: u32) -> Option<usize> {
+        self.indices.get(&id).copied()
+    }
+
+    fn handle_of(&self, id: u32) -> Option<RigidBodyHandle> {
+        self.index_of(id).map(|index| self.objects[index].handle())
+    }
+
+    /// Advance the simulation by one frame, calling the 'update' and 'hit' functions of the objects
+    pub fn step(&mut self) {
+        let time = set_clock(&self.env, self.num_updates, self.physics.dt());
+        if let Some(on_frame) = self.hooks.on_frame.clone() {
+            self.run_function(on_frame, vec![RuntimeValue::Number(self.num_updates as f32), RuntimeValue::Number(time)], true);
+        }
+
+        // Timer functions see the variables where they were declared, like the functions of objects
+        for func in self.env.due_timers(time, self.physics.dt()) {
+            let mut scope = Environment::new(func.declaration_env.clone(), true);
+            for statement in func.body {
+                evaluate(statement, &mut scope);
+            }
+        }
+
+        // 'world' is synced after the step, so only objects changed by their callbacks are synced during it
+        for index in 0..self.objects.len() {
+            if self.objects[index].update(&mut self.physics) {
+                self.sync_object(index);
+            }
+        }
+
+        self.hits.clear();
+        self.collisions.clear();
+
+        let touching_before = self.touching_objects();
+
+        // Forces only act for one step, so they are applied as impulses over it
+        self.apply_forces();
+        let events = self.physics.step();
+        self.break_joints();
+
+        // Contact callbacks see the objects where the step left them, also through 'world'
+        self.sync_world();
+
+        for event in events {
+            match event {
+                ContactEvent::Started(collider1, collider2, contact) => {
+                    let (first, second) = match (self.find_object(collider1), self.find_object(collider2)) {
+                        (Some(first), Some(second)) => (first, second),
+                        _ => continue
+                    };
+
+                    let (first_id, second_id) = (self.objects[first].id(), self.objects[second].id());
+                    self.contacts.insert(contact_key(collider1, collider2), (first_id, second_id));
+
+                    let (first_map, second_map) = (self.object_value(first), self.object_value(second));
+                    if self.objects[first].hit(&mut self.physics, second_map, contact_value(contact)) {
+                        self.sync_object(first);
+                    }
+                    if self.objects[second].hit(&mut self.physics, first_map, contact_value(contact.flipped())) {
+                        self.sync_object(second);
+                    }
+
+                    if let Some(on_collision) = self.hooks.on_collision.clone() {
+                        let (first_map, second_map) = (self.object_value(first), self.object_value(second));
+                        self.run_function(on_collision, vec![first_map, second_map], true);
+                    }
+
+                    self.hits.extend([first_id, second_id]);
+                    self.collisions.push(CollisionEvent { first: first_id, second: second_id, contact });
+                }
+                ContactEvent::Stopped(collider1, collider2) => {
+                    self.contacts.remove(&contact_key(collider1, collider2));
+                }
+            }
+        }
+
+        // Objects can touch with multiple colliders, so they only stop touching when none of them touch anymore
+        let touching_after = self.touching_objects();
+
+        for &(first, second) in touching_before.difference(&touching_after) {
+            let (Some(first), Some(second)) = (self.index_of(first), self.index_of(second)) else {
+                continue
+            };
+            if !self.objects[first].has_hit_end() && !self.objects[second].has_hit_end() {
+                continue
+            }
+
+            let (first_map, second_map) = (self.object_value(first), self.object_value(second));
+            if self.objects[first].hit_end(&mut self.physics, second_map) {
+                self.sync_object(first);
+            }
+            if self.objects[second].hit_end(&mut self.physics, first_map) {
+                self.sync_object(second);
+            }
+        }
+
+        // Piles of objects touch in many pairs, so the maps are only made for pairs with a callback
+        for &(first, second) in &touching_after {
+            let (Some(first), Some(second)) = (self.index_of(first), self.index_of(second)) else {
+                continue
+            };
+            if !self.objects[first].has_touching() && !self.objects[second].has_touching() {
+                continue
+            }
+
+            let (first_map, second_map) = (self.object_value(first), self.object_value(second));
+            if self.objects[first].touching(&mut self.physics, second_map) {
+                self.sync_object(first);
+            }
+            if self.objects[second].touching(&mut self.physics, first_map) {
+                self.sync_object(second);
+            }
+        }
+
+        // Objects spawned or destroyed during the frame are in the state right after it
+        self.apply_pending();
+
+        // After a step 'frame' is the number of steps taken, like 'Scene::frame'
+        self.num_updates += 1;
+        set_clock(&self.env, self.num_updates, self.physics.dt());
+    }
+
+    /// The ids of all pairs of objects that are touching
+    fn touching_objects(&self) -> BTreeSet<(u32, u32)> {
+        self.contacts.values().map(|&(first, second)| (first.min(second), first.max(second))).collect()
+    }
+
+    fn object_value(&self, index: usize) -> RuntimeValue {
+        RuntimeValue::Object(self.objects[index].to_map(&self.physics))
+    }
+
+    fn find_object(&self, collider: ColliderHandle) -> Option<usize> {
+        let body = self.physics.colliders.get(collider)?.parent()?;
+        self.index_of(*self.bodies.get(&body)?)
+    }
+
+    /// The number of steps taken so far
+    pub fn frame(&self) -> u128 {
+        self.num_updates
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// The object with the id, if it's still in the simulation
+    pub fn object(&self, id: u32) -> Option<&Object> {
+        self.index_of(id).map(|index| &self.objects[index])
+    }
+
+    pub fn object_states(&self) -> Vec<ObjectState> {
+        self.objects.iter().map(|object| {
+            let (pos, rotation) = object.get_pos_and_rot(&self.physics);
+
+            ObjectState {
+                x: pos.x,
+                y: pos.y,
+                rotation,
+                properties: object.to_map(&self.physics)
+            }
+        }).collect()
+    }
+
+    /// The collisions that started during the last step
+    pub fn collisions(&self) -> &[CollisionEvent] {
+        &self.collisions
+    }
+
+    /// The ids of the objects that were hit during the last step
+    pub fn hits(&self) -> &[u32] {
+        &self.hits
+    }
+
+    pub fn background_color(&self) -> Color {
+        match self.env.lookup_var("background_color".to_string()) {
+            RuntimeValue::Color(color) => color,
+            value => panic!("Invalid value for background: {:?}", value)
+        }
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.env
+    }
+
+    /// Call a function declared in the script, 'objects' and 'world' contain the current state of all objects
+    pub fn call(&mut self, name: &str, args: Vec<RuntimeValue>) -> RuntimeValue {
+        let values = self.objects.iter().map(|object| RuntimeValue::Object(object.to_map(&self.physics))).collect();
+        self.env.assign_var("objects".to_string(), RuntimeValue::Objects(values));
+        self.sync_world();
+
+        let func = match self.env.lookup_var(name.to_string()) {
+            RuntimeValue::Function(func) => func,
+            value => panic!("'{}' is not a function: {:?}", name, value)
+        };
+
+        if func.parameters.len() != args.len() {
+            panic!("The function '{}' takes {} arguments but {} were given", name, func.parameters.len(), args.len())
+        }
+
+        self.run_function(func, args, false)
+    }
+
+    /// Call the 'on_exit' function of the script, should be called once when the simulation stops
+    pub fn exit(&mut self) {
+        if let Some(on_exit) = self.hooks.on_exit.clone() {
+            self.run_function(on_exit, vec![], true);
+        }
+    }
+
+    /// Run the body of a function in the current global environment, so it can see everything declared after it.
+    /// Trailing parameters can be left out, like the parameters of callbacks
+    fn run_function(&self, func: Function, args: Vec<RuntimeValue>, simulation_running: bool) -> RuntimeValue {
+        if func.parameters.len() > args.len() {
+            panic!("The function '{}' takes {} arguments but only {} are given", func.name, func.parameters.len(), args.len())
+        }
+
+        let mut scope = Environment::new(self.env.clone(), simulation_running);
+        for (parameter, value) in func.parameters.into_iter().zip(args) {
+            scope.declare_var(parameter, value);
+        }
+
+        let mut result = RuntimeValue::Number(0.0);
+        for statement in func.body {
+            result = evaluate(statement, &mut scope);
+        }
+
+        result
+    }
+
+    #[cfg(feature = "window")]
+    pub fn draw(&self, draw: &nannou::Draw) {
+        for joint in &self.joints {
+            if let Some(points) = self.physics.joint_points(joint.handle) {
+                let points = points.map(|point| nannou::geom::pt2(point.x, point.y));
+                draw.polyline().weight(2.0).color(nannou::color::GRAY).points(points);
+            }
+        }
+
+        for object in &self.objects {
+            object.draw(draw, &self.physics);
+        }
+    }
+}
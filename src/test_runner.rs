@@ -0,0 +1,177 @@
+use std::{any::Any, fs, panic, path::{Path, PathBuf}, process};
+
+use phyx_script::{frontend::{ast::Statement, Parser}, simulation::{find_assets_path, note_names, SceneBuilder}};
+
+/// Run every 'fn test_*()' in the given scripts, usage: phyx test [--frames N] <files or directories>.
+/// A script can set its own number of frames with 'let test_frames = N', which takes precedence over '--frames'
+pub fn run(args: &[String]) {
+    let mut frames = 0;
+    let mut paths = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--frames" {
+            frames = match args.next().map(|number| number.parse()) {
+                Some(Ok(number)) => number,
+                _ => panic!("Expected a number of frames after '--frames'")
+            };
+        } else {
+            collect_scripts(Path::new(arg), &mut paths);
+        }
+    }
+
+    if paths.is_empty() {
+        panic!("Please input a file or directory to test")
+    }
+
+    let assets_path = find_assets_path().expect("Failed to find assets directory");
+    let notes = note_names(&assets_path);
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut passed = 0;
+    let mut failed = vec![];
+
+    for path in paths {
+        let code = fs::read_to_string(&path).expect("Failed to read file");
+        let ast = match panic::catch_unwind(|| Parser::new().produce_ast(code.clone())) {
+            Ok(ast) => ast,
+            Err(payload) => {
+                println!("test {} ... FAILED", path.display());
+                failed.push((path.display().to_string(), panic_message(payload)));
+                continue
+            }
+        };
+
+        let settings = panic::catch_unwind(|| (test_frames(&ast), fixtures(&ast, &path)));
+        let (frames, code) = match settings {
+            Ok((test_frames, fixtures)) => (test_frames.unwrap_or(frames), fixtures + &code),
+            Err(payload) => {
+                println!("test {} ... FAILED", path.display());
+                failed.push((path.display().to_string(), panic_message(payload)));
+                continue
+            }
+        };
+
+        for name in test_names(&ast) {
+            let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                run_test(code.clone(), &name, notes.clone(), frames)
+            }));
+
+            match result {
+                Ok(()) => {
+                    println!("test {}::{} ... ok", path.display(), name);
+                    passed += 1;
+                }
+                Err(payload) => {
+                    println!("test {}::{} ... FAILED", path.display(), name);
+                    failed.push((format!("{}::{}", path.display(), name), panic_message(payload)));
+                }
+            }
+        }
+    }
+
+    panic::set_hook(default_hook);
+
+    for (name, message) in &failed {
+        println!("\n---- {} ----\n{}", name, message);
+    }
+
+    let status = if failed.is_empty() { "ok" } else { "FAILED" };
+    println!("\ntest result: {}. {} passed; {} failed", status, passed, failed.len());
+
+    if !failed.is_empty() {
+        process::exit(1);
+    }
+}
+
+fn collect_scripts(path: &Path, paths: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = path.read_dir().expect("Failed to read dir")
+            .map(|entry| entry.expect("Invalid file").path())
+            .collect();
+        entries.sort();
+
+        for entry in entries {
+            if entry.is_dir() || entry.extension().is_some_and(|extension| extension == "phyx") {
+                collect_scripts(&entry, paths);
+            }
+        }
+    } else {
+        paths.push(path.to_path_buf());
+    }
+}
+
+fn test_names(ast: &Statement) -> Vec<String> {
+    let body = match ast {
+        Statement::Program { body } => body,
+        statement => panic!("Expected a program, got: {:?}", statement)
+    };
+
+    body.iter().filter_map(|statement| match statement {
+        Statement::FunctionDeclaration { name, parameters, body: _ } if name.starts_with("test_") && parameters.is_empty() => Some(name.clone()),
+        _ => None
+    }).collect()
+}
+
+fn top_level(ast: &Statement, identifier: &str) -> Option<Statement> {
+    let Statement::Program { body } = ast else {
+        return None
+    };
+
+    body.iter().find_map(|statement| match statement {
+        Statement::VarDeclaration { identifier: name, value } if name == identifier => Some(*value.clone()),
+        _ => None
+    })
+}
+
+/// The frames set by 'let test_frames = N' in the script
+fn test_frames(ast: &Statement) -> Option<u128> {
+    match top_level(ast, "test_frames")? {
+        Statement::NumericLiteral(frames) if frames >= 0.0 => Some(frames as u128),
+        value => panic!("Expected a number of frames for 'test_frames', got: {:?}", value)
+    }
+}
+
+/// The code of the scripts listed in 'let test_fixtures = ["path"]', relative to the script, which runs before it
+fn fixtures(ast: &Statement, path: &Path) -> String {
+    let Some(value) = top_level(ast, "test_fixtures") else {
+        return String::new()
+    };
+
+    let Statement::List(paths) = value else {
+        panic!("Expected a list of paths for 'test_fixtures', got: {:?}", value)
+    };
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    paths.iter().map(|fixture| match fixture {
+        Statement::StringLiteral(fixture) => {
+            let code = fs::read_to_string(dir.join(fixture)).unwrap_or_else(|_| panic!("Failed to read fixture '{}'", fixture));
+            code + "\n"
+        }
+        value => panic!("Expected a path in 'test_fixtures', got: {:?}", value)
+    }).collect()
+}
+
+/// Evaluate the script in a fresh environment, simulate it for some frames, stop it and then call the test
+fn run_test(code: String, name: &str, notes: Vec<String>, frames: u128) {
+    let mut scene = SceneBuilder::new().notes(notes).build(code);
+
+    for _ in 0..frames {
+        scene.step();
+    }
+    scene.exit();
+
+    scene.call(name, vec![]);
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
@@ -0,0 +1,55 @@
+use std::panic;
+
+use phyx_script::{runtime::RuntimeValue, simulation::SceneBuilder};
+
+/// Blocks inside a callback can change 'self' and the variables of the callback
+#[test]
+fn blocks_in_callbacks_change_self_and_locals() {
+    let mut scene = SceneBuilder::new().build(r#"
+fn update(self) {
+    let count = 0
+    if (true) {
+        count += 1
+    }
+    for i in range(2) {
+        count += 1
+    }
+    while (count < 5) {
+        count += 1
+    }
+    if (true) {
+        self.count = count
+    }
+}
+
+objects.add(object { update, count: 0 })
+"#.to_string());
+
+    scene.step();
+
+    let count = scene.object_states()[0].properties.get("count").cloned();
+    assert_eq!(count, Some(RuntimeValue::Number(5.0)));
+}
+
+/// Blocks inside a callback still can't change the variables declared with 'let' outside of it
+#[test]
+fn blocks_in_callbacks_cannot_change_outer_variables() {
+    for block in ["if (true) { counter += 1 }", "for i in range(1) { counter += 1 }", "while (true) { counter += 1 }"] {
+        let mut scene = SceneBuilder::new().build(format!(r#"
+let counter = 0
+
+fn update(self) {{
+    {}
+}}
+
+objects.add(object {{ update }})
+"#, block));
+
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| scene.step()));
+        panic::set_hook(default_hook);
+
+        assert!(result.is_err(), "'{}' should fail in 'update'", block);
+    }
+}